@@ -1,9 +1,11 @@
 //! Command-line interface for publishing interdependent Cargo workspace packages.
 
-use clap::Parser;
+use clap::builder::BoolishValueParser;
+use clap::{Parser, Subcommand};
 use color_eyre::eyre::{self, eyre};
 use publish_crates as publish;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::Duration;
 
 fn parse_duration_string(duration: &str) -> eyre::Result<Duration> {
@@ -12,6 +14,56 @@ fn parse_duration_string(duration: &str) -> eyre::Result<Duration> {
         .map_err(|_| eyre!("{} is not a valid duration", duration))
 }
 
+fn parse_graph_format(format: &str) -> eyre::Result<publish::GraphFormat> {
+    Ok(format.parse()?)
+}
+
+fn parse_version_req_style(style: &str) -> eyre::Result<publish::VersionReqStyle> {
+    Ok(style.parse()?)
+}
+
+fn parse_bump_level(level: &str) -> eyre::Result<publish::BumpLevel> {
+    Ok(level.parse()?)
+}
+
+fn parse_retry_class_delay(value: &str) -> eyre::Result<(publish::RetryClass, Duration)> {
+    let (class, delay) = value
+        .split_once('=')
+        .ok_or_else(|| eyre!("{value:?} is not of the form CLASS=DURATION"))?;
+    Ok((class.parse()?, parse_duration_string(delay)?))
+}
+
+fn parse_message_format(format: &str) -> eyre::Result<publish::MessageFormat> {
+    Ok(format.parse()?)
+}
+
+/// Prints human-readable output, to stderr when stdout carries JSON events.
+fn print_human(format: publish::MessageFormat, output: &impl std::fmt::Display) {
+    if format == publish::MessageFormat::Json {
+        eprint!("{output}");
+    } else {
+        print!("{output}");
+    }
+}
+
+/// Interrupts publishing on Ctrl-C, and exits on a second Ctrl-C without waiting for the
+/// manifests to be restored.
+fn interrupt_on_ctrl_c() -> Arc<tokio::sync::Notify> {
+    let interrupt = Arc::new(tokio::sync::Notify::new());
+    let notify = Arc::clone(&interrupt);
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        eprintln!("interrupted, stopping (press Ctrl-C again to exit now)");
+        notify.notify_one();
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(130);
+        }
+    });
+    interrupt
+}
+
 const ENV_PREFIX: &str = "PUBLISH_CRATES";
 
 #[derive(Parser, Debug, Clone)]
@@ -22,34 +74,173 @@ const ENV_PREFIX: &str = "PUBLISH_CRATES";
     author = "romnn <contact@romnn.com>",
 )]
 struct Options {
-    #[clap(short = 'p', long = "path", env = format!("{ENV_PREFIX}_CRATE_PATH"))]
+    #[clap(subcommand)]
+    command: Option<Command>,
+    #[clap(short = 'p', long = "path", global = true, env = format!("{ENV_PREFIX}_CRATE_PATH"))]
     path: Option<PathBuf>,
-    #[clap(long = "registry-token", env = format!("{ENV_PREFIX}_REGISTRY_TOKEN"))]
+    #[clap(long = "registry-token", global = true, env = format!("{ENV_PREFIX}_REGISTRY_TOKEN"))]
     registry_token: Option<String>,
-    #[clap(long = "dry-run", env = format!("{ENV_PREFIX}_DRY_RUN"))]
+    #[clap(long = "dry-run", global = true, env = format!("{ENV_PREFIX}_DRY_RUN"))]
     dry_run: bool,
     #[clap(
         long = "publish-delay",
+        global = true,
         env = format!("{ENV_PREFIX}_PUBLISH_DELAY"),
         value_parser = parse_duration_string,
     )]
     publish_delay: Option<Duration>,
-    #[clap(long = "max-retries", env = format!("{ENV_PREFIX}_MAX_RETRIES"))]
+    #[clap(long = "max-retries", global = true, env = format!("{ENV_PREFIX}_MAX_RETRIES"))]
     max_retries: Option<usize>,
-    #[clap(long = "concurrency-limit", env = format!("{ENV_PREFIX}_CONCURRENCY_LIMIT"))]
+    #[clap(
+        long = "retry-initial-delay",
+        global = true,
+        env = format!("{ENV_PREFIX}_RETRY_INITIAL_DELAY"),
+        value_parser = parse_duration_string,
+    )]
+    retry_initial_delay: Option<Duration>,
+    #[clap(long = "retry-multiplier", global = true, env = format!("{ENV_PREFIX}_RETRY_MULTIPLIER"))]
+    retry_multiplier: Option<f64>,
+    #[clap(
+        long = "retry-max-delay",
+        global = true,
+        env = format!("{ENV_PREFIX}_RETRY_MAX_DELAY"),
+        value_parser = parse_duration_string,
+    )]
+    retry_max_delay: Option<Duration>,
+    #[clap(long = "retry-jitter", global = true, env = format!("{ENV_PREFIX}_RETRY_JITTER"))]
+    retry_jitter: Option<f64>,
+    #[clap(
+        long = "retry-budget",
+        global = true,
+        env = format!("{ENV_PREFIX}_RETRY_BUDGET"),
+        value_parser = parse_duration_string,
+    )]
+    retry_budget: Option<Duration>,
+    /// Initial delay for a class of failures (rate-limited, retryable or unknown), as CLASS=DURATION.
+    #[clap(
+        long = "retry-delay",
+        global = true,
+        value_delimiter = ',',
+        env = format!("{ENV_PREFIX}_RETRY_DELAYS"),
+        value_parser = parse_retry_class_delay,
+    )]
+    retry_class_delays: Vec<(publish::RetryClass, Duration)>,
+    #[clap(long = "concurrency-limit", global = true, env = format!("{ENV_PREFIX}_CONCURRENCY_LIMIT"))]
     concurrency_limit: Option<usize>,
-    #[clap(long = "no-verify", env = format!("{ENV_PREFIX}_NO_VERIFY"))]
-    no_verify: bool,
-    #[clap(long = "resolve-versions", env = format!("{ENV_PREFIX}_RESOLVE_VERSIONS"))]
-    resolve_versions: bool,
-    #[clap(long = "include", env = format!("{ENV_PREFIX}_INCLUDE_PACKAGES"))]
+    #[clap(
+        long = "no-verify",
+        global = true,
+        env = format!("{ENV_PREFIX}_NO_VERIFY"),
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new(),
+    )]
+    no_verify: Option<bool>,
+    #[clap(
+        long = "resolve-versions",
+        global = true,
+        env = format!("{ENV_PREFIX}_RESOLVE_VERSIONS"),
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new(),
+    )]
+    resolve_versions: Option<bool>,
+    #[clap(
+        long = "version-req-style",
+        global = true,
+        env = format!("{ENV_PREFIX}_VERSION_REQ_STYLE"),
+        value_parser = parse_version_req_style,
+    )]
+    version_req_style: Option<publish::VersionReqStyle>,
+    #[clap(long = "include", global = true, env = format!("{ENV_PREFIX}_INCLUDE_PACKAGES"))]
     include: Option<Vec<String>>,
-    #[clap(long = "exclude", env = format!("{ENV_PREFIX}_EXCLUDE_PACKAGES"))]
+    #[clap(long = "exclude", global = true, env = format!("{ENV_PREFIX}_EXCLUDE_PACKAGES"))]
     exclude: Option<Vec<String>>,
+    #[clap(
+        long = "include-dependencies",
+        global = true,
+        env = format!("{ENV_PREFIX}_INCLUDE_DEPENDENCIES"),
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new(),
+    )]
+    include_dependencies: Option<bool>,
+    #[clap(long = "changed-since", global = true, env = format!("{ENV_PREFIX}_CHANGED_SINCE"))]
+    changed_since: Option<String>,
+    #[clap(
+        long = "include-dependants",
+        global = true,
+        env = format!("{ENV_PREFIX}_INCLUDE_DEPENDANTS"),
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new(),
+    )]
+    include_dependants: Option<bool>,
+    #[clap(long = "state-file", global = true, env = format!("{ENV_PREFIX}_STATE_FILE"))]
+    state_file: Option<PathBuf>,
+    #[clap(long = "resume", global = true, env = format!("{ENV_PREFIX}_RESUME"))]
+    resume: bool,
+    #[clap(
+        long = "message-format",
+        global = true,
+        default_value = "human",
+        env = format!("{ENV_PREFIX}_MESSAGE_FORMAT"),
+        value_parser = parse_message_format,
+    )]
+    message_format: publish::MessageFormat,
+    #[clap(
+        long = "restore-manifests",
+        global = true,
+        env = format!("{ENV_PREFIX}_RESTORE_MANIFESTS"),
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new(),
+    )]
+    restore_manifests: Option<bool>,
     #[clap(last = true, value_name = "CARGO_PUBLISH_ARGS")]
     extra_args: Vec<String>,
 }
 
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+enum Command {
+    /// Print the publish order without modifying manifests or publishing.
+    Plan {
+        /// Print the dependency graph in this format (dot, mermaid or json) instead.
+        #[clap(long = "graph-format", value_parser = parse_graph_format)]
+        graph_format: Option<publish::GraphFormat>,
+        #[clap(last = true, value_name = "CARGO_PUBLISH_ARGS")]
+        extra_args: Vec<String>,
+    },
+    /// Bump workspace package versions and the local dependency requirements on them.
+    Bump {
+        /// major, minor, patch, prerelease or an explicit version such as 1.2.3.
+        #[clap(value_parser = parse_bump_level)]
+        level: publish::BumpLevel,
+    },
+}
+
+impl Options {
+    /// Applies the retry options to the default [`publish::RetryPolicy`].
+    fn retry_policy(&self) -> publish::RetryPolicy {
+        let default = publish::RetryPolicy::default();
+        let mut class_delays = default.class_delays;
+        class_delays.extend(self.retry_class_delays.iter().copied());
+        publish::RetryPolicy {
+            initial_delay: self.retry_initial_delay.unwrap_or(default.initial_delay),
+            multiplier: self.retry_multiplier.unwrap_or(default.multiplier),
+            max_delay: self.retry_max_delay.unwrap_or(default.max_delay),
+            jitter: self.retry_jitter.unwrap_or(default.jitter),
+            budget: self.retry_budget.or(default.budget),
+            class_delays,
+        }
+    }
+}
+
 impl TryFrom<Options> for publish::Options {
     type Error = std::io::Error;
 
@@ -66,18 +257,31 @@ impl TryFrom<Options> for publish::Options {
             },
         );
 
+        let retry_policy = options.retry_policy();
         Ok(publish::Options {
             path,
             registry_token: options.registry_token,
             dry_run: options.dry_run,
             publish_delay: options.publish_delay,
             max_retries: options.max_retries,
+            retry_policy,
             concurrency_limit: options.concurrency_limit,
             no_verify: options.no_verify,
             resolve_versions: options.resolve_versions,
+            version_req_style: options.version_req_style,
             include: options.include,
             exclude: options.exclude,
+            include_dependencies: options.include_dependencies,
+            changed_since: options.changed_since,
+            include_dependants: options.include_dependants,
             extra_args: options.extra_args,
+            registry_client: None,
+            state_file: options.state_file,
+            resume: options.resume,
+            message_format: options.message_format,
+            observer: None,
+            restore_manifests: options.restore_manifests,
+            interrupt: None,
         })
     }
 }
@@ -86,16 +290,59 @@ impl TryFrom<Options> for publish::Options {
 async fn main() -> eyre::Result<()> {
     color_eyre::install()?;
 
-    let options: publish::Options = Options::parse().try_into()?;
-    publish::publish(options).await?;
+    // Cargo passes the subcommand name when invoked as `cargo publish-crates`.
+    let args = std::env::args_os()
+        .enumerate()
+        .filter(|(index, arg)| *index != 1 || arg != "publish-crates")
+        .map(|(_, arg)| arg);
+    let mut options = Options::parse_from(args);
+    let command = options.command.take();
+    if let Some(Command::Plan { extra_args, .. }) = &command {
+        options.extra_args.extend(extra_args.iter().cloned());
+    }
+    let message_format = options.message_format;
+    let mut options: publish::Options = options.try_into()?;
+    match command {
+        Some(Command::Plan {
+            graph_format: Some(format),
+            ..
+        }) => {
+            let graph = publish::graph(options, format)
+                .await
+                .map_err(|err| eyre!("{}", err.display_with_suggestion()))?;
+            print_human(message_format, &graph);
+        }
+        Some(Command::Plan {
+            graph_format: None, ..
+        }) => {
+            let plan = publish::plan(options)
+                .await
+                .map_err(|err| eyre!("{}", err.display_with_suggestion()))?;
+            print_human(message_format, &plan);
+        }
+        Some(Command::Bump { level }) => {
+            let bumped = publish::bump(options, &level)
+                .await
+                .map_err(|err| eyre!("{}", err.display_with_suggestion()))?;
+            print_human(message_format, &bumped);
+        }
+        None => {
+            options.interrupt = Some(interrupt_on_ctrl_c());
+            let published = publish::publish(options)
+                .await
+                .map_err(|err| eyre!("{}", err.display_with_suggestion()))?;
+            print_human(message_format, &published);
+        }
+    }
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Options;
+    use super::{Command, Options};
     use clap::Parser;
     use similar_asserts::assert_eq as sim_assert_eq;
+    use std::time::Duration;
 
     #[test]
     fn parses_selection_and_cargo_arguments() {
@@ -104,23 +351,146 @@ mod tests {
             "--dry-run",
             "--include",
             "core",
+            "--include",
+            "crates/tools/**",
+            "--include-dependencies",
             "--exclude",
             "internal",
+            "--message-format",
+            "json",
             "--",
             "--registry",
             "private",
         ])
         .expect("arguments must parse");
 
-        sim_assert_eq!(options.include, Some(vec!["core".to_string()]));
+        sim_assert_eq!(
+            options.include,
+            Some(vec!["core".to_string(), "crates/tools/**".to_string()])
+        );
         sim_assert_eq!(options.exclude, Some(vec!["internal".to_string()]));
+        sim_assert_eq!(options.include_dependencies, Some(true));
         sim_assert_eq!(
             options.extra_args,
             vec!["--registry".to_string(), "private".to_string()]
         );
+        sim_assert_eq!(options.message_format, publish_crates::MessageFormat::Json);
         assert!(options.dry_run);
     }
 
+    #[test]
+    fn parses_flags_that_override_the_configuration() {
+        let options = Options::try_parse_from([
+            "cargo-publish-crates",
+            "--no-verify",
+            "--resolve-versions=false",
+            "--restore-manifests=no",
+        ])
+        .expect("arguments must parse");
+
+        sim_assert_eq!(options.no_verify, Some(true));
+        sim_assert_eq!(options.resolve_versions, Some(false));
+        sim_assert_eq!(options.restore_manifests, Some(false));
+        sim_assert_eq!(options.include_dependants, None);
+    }
+
+    #[test]
+    fn parses_plan_subcommand_with_options() {
+        let options = Options::try_parse_from([
+            "cargo-publish-crates",
+            "--include",
+            "core",
+            "plan",
+            "--exclude",
+            "internal",
+            "--graph-format",
+            "mermaid",
+            "--",
+            "--registry",
+            "private",
+        ])
+        .expect("arguments must parse");
+
+        sim_assert_eq!(
+            options.command,
+            Some(Command::Plan {
+                graph_format: Some(publish_crates::GraphFormat::Mermaid),
+                extra_args: vec!["--registry".to_string(), "private".to_string()]
+            })
+        );
+        sim_assert_eq!(options.include, Some(vec!["core".to_string()]));
+        sim_assert_eq!(options.exclude, Some(vec!["internal".to_string()]));
+
+        let options =
+            Options::try_parse_from(["cargo-publish-crates"]).expect("arguments must parse");
+        sim_assert_eq!(options.command, None);
+    }
+
+    #[test]
+    fn parses_bump_subcommand() {
+        let options = Options::try_parse_from([
+            "cargo-publish-crates",
+            "--dry-run",
+            "bump",
+            "1.3.0-rc.1",
+            "--changed-since",
+            "v1.2.0",
+        ])
+        .expect("arguments must parse");
+
+        sim_assert_eq!(
+            options.command,
+            Some(Command::Bump {
+                level: publish_crates::BumpLevel::Version(
+                    "1.3.0-rc.1".parse().expect("version must parse")
+                ),
+            })
+        );
+        sim_assert_eq!(options.changed_since, Some("v1.2.0".to_string()));
+        assert!(options.dry_run);
+        assert!(
+            Options::try_parse_from(["cargo-publish-crates", "bump", "huge"]).is_err(),
+            "unknown bump levels must be rejected"
+        );
+    }
+
+    #[test]
+    fn parses_retry_policy() {
+        let options = Options::try_parse_from([
+            "cargo-publish-crates",
+            "--retry-initial-delay",
+            "5s",
+            "--retry-jitter",
+            "0",
+            "--retry-budget",
+            "1h",
+            "--retry-delay",
+            "rate-limited=1m,unknown=10s",
+        ])
+        .expect("arguments must parse");
+        sim_assert_eq!(
+            options.retry_policy(),
+            publish_crates::RetryPolicy {
+                initial_delay: Duration::from_secs(5),
+                jitter: 0.0,
+                budget: Some(Duration::from_hours(1)),
+                class_delays: [
+                    (
+                        publish_crates::RetryClass::RateLimited,
+                        Duration::from_mins(1)
+                    ),
+                    (publish_crates::RetryClass::Unknown, Duration::from_secs(10)),
+                ]
+                .into(),
+                ..publish_crates::RetryPolicy::default()
+            }
+        );
+        assert!(
+            Options::try_parse_from(["cargo-publish-crates", "--retry-delay", "slow=1m"]).is_err(),
+            "unknown retry classes must be rejected"
+        );
+    }
+
     #[test]
     fn converts_directory_and_manifest_paths() {
         let temp = tempfile::tempdir().expect("temporary directory must be created");
@@ -0,0 +1,63 @@
+//! Output streams of `--message-format json`.
+
+use similar_asserts::assert_eq as sim_assert_eq;
+
+/// Keeps stdout parseable as newline-delimited JSON, with log lines and the report on stderr.
+#[test]
+fn json_message_format_writes_only_events_to_stdout() {
+    let temp = tempfile::tempdir().expect("temporary workspace must be created");
+    let package_dir = temp.path().join("crates").join("internal");
+    std::fs::create_dir_all(package_dir.join("src")).expect("package directory must be created");
+    std::fs::write(
+        temp.path().join("Cargo.toml"),
+        r#"[workspace]
+members = ["crates/internal"]
+resolver = "2"
+"#,
+    )
+    .expect("workspace manifest must be written");
+    std::fs::write(
+        package_dir.join("Cargo.toml"),
+        r#"[package]
+name = "internal"
+version = "1.2.3"
+edition = "2021"
+publish = false
+"#,
+    )
+    .expect("package manifest must be written");
+    std::fs::write(package_dir.join("src/lib.rs"), "").expect("package source must be written");
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_cargo-publish-crates"))
+        .arg("--path")
+        .arg(temp.path())
+        .args(["--message-format", "json"])
+        .env_remove("PUBLISH_CRATES_MESSAGE_FORMAT")
+        .output()
+        .expect("cargo-publish-crates must run");
+    assert!(
+        output.status.success(),
+        "publishing must succeed: {output:?}"
+    );
+
+    let stdout = String::from_utf8(output.stdout).expect("stdout must be UTF-8");
+    let reasons = stdout
+        .lines()
+        .map(|line| {
+            let event: serde_json::Value = serde_json::from_str(line)
+                .unwrap_or_else(|err| panic!("stdout line {line:?} must be JSON: {err}"));
+            event["reason"].as_str().unwrap_or_default().to_string()
+        })
+        .collect::<Vec<_>>();
+    sim_assert_eq!(reasons, vec!["plan", "skipped", "run_finished"]);
+
+    let stderr = String::from_utf8(output.stderr).expect("stderr must be UTF-8");
+    assert!(
+        stderr.contains("[internal@1.2.3] skipping (publish = false)"),
+        "log lines must go to stderr: {stderr}"
+    );
+    assert!(
+        stderr.contains("skipped (publish = false)"),
+        "the report must go to stderr: {stderr}"
+    );
+}
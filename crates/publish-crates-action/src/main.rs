@@ -2,9 +2,10 @@
 
 use action_core::{self as action};
 use color_eyre::eyre::{self, WrapErr};
-use publish_crates::{Options, publish};
+use publish_crates::{MessageFormat, Options, RetryClass, RetryPolicy, VersionReqStyle, publish};
 use std::ffi::OsString;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 fn parse_package_names(value: Option<String>) -> Option<Vec<String>> {
     let packages = value?
@@ -24,6 +25,22 @@ fn parse_extra_args(value: Option<String>) -> eyre::Result<Vec<String>> {
     )
 }
 
+fn parse_retry_delays(
+    value: Option<String>,
+) -> eyre::Result<Vec<(RetryClass, std::time::Duration)>> {
+    parse_package_names(value)
+        .unwrap_or_default()
+        .iter()
+        .map(|entry| {
+            let (class, delay) = entry
+                .split_once('=')
+                .ok_or_else(|| eyre::eyre!("{entry:?} is not of the form CLASS=DURATION"))?;
+            let delay = <Duration as action::input::Parse>::parse(delay.into())?;
+            Ok((class.parse()?, delay.into()))
+        })
+        .collect()
+}
+
 struct Duration(std::time::Duration);
 
 impl From<Duration> for std::time::Duration {
@@ -60,6 +77,64 @@ use action_definition::PublishCratesAction;
 #[cfg(test)]
 use action_definition::PublishCratesActionInput;
 
+fn retry_policy() -> eyre::Result<RetryPolicy> {
+    let duration = |value: Option<Duration>| value.map(std::time::Duration::from);
+    let default = RetryPolicy::default();
+    let mut class_delays = default.class_delays;
+    class_delays.extend(
+        parse_retry_delays(PublishCratesAction::retry_delays::<String>()?)
+            .wrap_err("invalid value for retry-delays")?,
+    );
+    Ok(RetryPolicy {
+        initial_delay: duration(
+            PublishCratesAction::retry_initial_delay::<Duration>()
+                .wrap_err("invalid value for retry-initial-delay")?,
+        )
+        .unwrap_or(default.initial_delay),
+        multiplier: PublishCratesAction::retry_multiplier::<String>()?
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .wrap_err("invalid value for retry-multiplier")?
+            .unwrap_or(default.multiplier),
+        max_delay: duration(
+            PublishCratesAction::retry_max_delay::<Duration>()
+                .wrap_err("invalid value for retry-max-delay")?,
+        )
+        .unwrap_or(default.max_delay),
+        jitter: PublishCratesAction::retry_jitter::<String>()?
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .wrap_err("invalid value for retry-jitter")?
+            .unwrap_or(default.jitter),
+        budget: duration(
+            PublishCratesAction::retry_budget::<Duration>()
+                .wrap_err("invalid value for retry-budget")?,
+        )
+        .or(default.budget),
+        class_delays,
+    })
+}
+
+/// Interrupts publishing on Ctrl-C, and exits on a second Ctrl-C without waiting for the
+/// manifests to be restored.
+fn interrupt_on_ctrl_c() -> Arc<tokio::sync::Notify> {
+    let interrupt = Arc::new(tokio::sync::Notify::new());
+    let notify = Arc::clone(&interrupt);
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        action::warning!("interrupted, stopping (press Ctrl-C again to exit now)");
+        notify.notify_one();
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(130);
+        }
+    });
+    interrupt
+}
+
 async fn run() -> eyre::Result<()> {
     color_eyre::install()?;
 
@@ -89,17 +164,28 @@ async fn run() -> eyre::Result<()> {
         .transpose()
         .wrap_err("invalid value for concurrency-limit")?;
 
-    let no_verify = PublishCratesAction::no_verify::<bool>()
-        .wrap_err("invalid value for option no-verify")?
-        .unwrap_or(false);
+    let no_verify =
+        PublishCratesAction::no_verify::<bool>().wrap_err("invalid value for option no-verify")?;
 
     let resolve_versions = PublishCratesAction::resolve_versions::<bool>()
-        .wrap_err("invalid value for option resolve-versions")?
-        .unwrap_or(false);
+        .wrap_err("invalid value for option resolve-versions")?;
+
+    let version_req_style = PublishCratesAction::version_req_style::<String>()?
+        .as_deref()
+        .map(str::parse::<VersionReqStyle>)
+        .transpose()
+        .wrap_err("invalid value for version-req-style")?;
 
     let include = parse_package_names(PublishCratesAction::include::<String>()?);
     let exclude = parse_package_names(PublishCratesAction::exclude::<String>()?);
+    let include_dependencies = PublishCratesAction::include_dependencies::<bool>()
+        .wrap_err("invalid value for option include-dependencies")?;
+    let changed_since = PublishCratesAction::changed_since::<String>()?
+        .filter(|git_ref| !git_ref.trim().is_empty());
+    let include_dependants = PublishCratesAction::include_dependants::<bool>()
+        .wrap_err("invalid value for option include-dependants")?;
     let extra_args = parse_extra_args(PublishCratesAction::extra_args::<String>()?)?;
+    let retry_policy = retry_policy()?;
 
     action::info!("include: {include:?}");
     action::info!("exclude: {exclude:?}");
@@ -110,14 +196,29 @@ async fn run() -> eyre::Result<()> {
         dry_run,
         publish_delay,
         max_retries,
+        retry_policy,
         concurrency_limit,
         no_verify,
         resolve_versions,
+        version_req_style,
         include,
         exclude,
+        include_dependencies,
+        changed_since,
+        include_dependants,
         extra_args,
+        registry_client: None,
+        state_file: None,
+        resume: false,
+        message_format: MessageFormat::Human,
+        observer: None,
+        restore_manifests: None,
+        interrupt: Some(interrupt_on_ctrl_c()),
     };
-    publish(options).await?;
+    let published = publish(options)
+        .await
+        .map_err(|err| eyre::eyre!("{}", err.display_with_suggestion()))?;
+    action::info!("{published}");
     Ok(())
 }
 
@@ -131,7 +232,8 @@ async fn main() {
 #[cfg(test)]
 mod tests {
     use super::{
-        PublishCratesAction, PublishCratesActionInput, parse_extra_args, parse_package_names,
+        PublishCratesAction, PublishCratesActionInput, RetryClass, parse_extra_args,
+        parse_package_names, parse_retry_delays,
     };
     use action_core::{self as action, Parse, input};
     use color_eyre::eyre;
@@ -187,21 +289,25 @@ mod tests {
                 Some("test-token".to_string()),
             ),
             (PublishCratesActionInput::MaxRetries, None),
-            (
-                PublishCratesActionInput::ConcurrencyLimit,
-                Some("4".to_string()),
-            ),
+            (PublishCratesActionInput::RetryInitialDelay, None),
+            (PublishCratesActionInput::RetryMultiplier, None),
+            (PublishCratesActionInput::RetryMaxDelay, None),
+            (PublishCratesActionInput::RetryJitter, None),
+            (PublishCratesActionInput::RetryBudget, None),
+            (PublishCratesActionInput::RetryDelays, None),
+            (PublishCratesActionInput::ConcurrencyLimit, None),
             (PublishCratesActionInput::ExtraArgs, None),
             (
                 PublishCratesActionInput::ResolveVersions,
                 Some("true".to_string()),
             ),
+            (PublishCratesActionInput::VersionReqStyle, None),
             (PublishCratesActionInput::Include, None),
-            (
-                PublishCratesActionInput::NoVerify,
-                Some("false".to_string()),
-            ),
+            (PublishCratesActionInput::NoVerify, None),
             (PublishCratesActionInput::Exclude, None),
+            (PublishCratesActionInput::ChangedSince, None),
+            (PublishCratesActionInput::IncludeDependencies, None),
+            (PublishCratesActionInput::IncludeDependants, None),
             (
                 PublishCratesActionInput::PublishDelay,
                 Some("30s".to_string()),
@@ -240,14 +346,24 @@ mod tests {
             INPUT_PATH: ${{ inputs.path }}
             INPUT_INCLUDE: ${{ inputs.include }}
             INPUT_EXCLUDE: ${{ inputs.exclude }}
+            INPUT_INCLUDE-DEPENDENCIES: ${{ inputs.include-dependencies }}
+            INPUT_CHANGED-SINCE: ${{ inputs.changed-since }}
+            INPUT_INCLUDE-DEPENDANTS: ${{ inputs.include-dependants }}
             INPUT_EXTRA-ARGS: ${{ inputs.extra-args }}
             INPUT_REGISTRY-TOKEN: ${{ inputs.registry-token }}
             INPUT_DRY-RUN: ${{ inputs.dry-run }}
             INPUT_PUBLISH-DELAY: ${{ inputs.publish-delay }}
             INPUT_CONCURRENCY-LIMIT: ${{ inputs.concurrency-limit }}
             INPUT_MAX-RETRIES: ${{ inputs.max-retries }}
+            INPUT_RETRY-INITIAL-DELAY: ${{ inputs.retry-initial-delay }}
+            INPUT_RETRY-MULTIPLIER: ${{ inputs.retry-multiplier }}
+            INPUT_RETRY-MAX-DELAY: ${{ inputs.retry-max-delay }}
+            INPUT_RETRY-JITTER: ${{ inputs.retry-jitter }}
+            INPUT_RETRY-BUDGET: ${{ inputs.retry-budget }}
+            INPUT_RETRY-DELAYS: ${{ inputs.retry-delays }}
             INPUT_NO-VERIFY: ${{ inputs.no-verify }}
             INPUT_RESOLVE-VERSIONS: ${{ inputs.resolve-versions }}
+            INPUT_VERSION-REQ-STYLE: ${{ inputs.version-req-style }}
         "})?;
 
         sim_assert_eq!(forwarded, &expected);
@@ -262,6 +378,21 @@ mod tests {
         sim_assert_eq!(parse_duration("1m30s"), Some(Duration::from_secs(90)));
     }
 
+    #[test]
+    fn retry_delays_accept_classes_and_durations() -> eyre::Result<()> {
+        sim_assert_eq!(
+            parse_retry_delays(Some("rate-limited=10M, unknown=1m30s".to_string()))?,
+            vec![
+                (RetryClass::RateLimited, Duration::from_mins(10)),
+                (RetryClass::Unknown, Duration::from_secs(90)),
+            ]
+        );
+        assert!(parse_retry_delays(Some("slow=1m".to_string())).is_err());
+        assert!(parse_retry_delays(Some("unknown".to_string())).is_err());
+        sim_assert_eq!(parse_retry_delays(None)?, vec![]);
+        Ok(())
+    }
+
     #[test]
     fn package_names_accept_commas_and_whitespace() {
         sim_assert_eq!(
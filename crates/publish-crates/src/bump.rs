@@ -0,0 +1,520 @@
+//! Version bumps of workspace packages and their local dependency requirements.
+
+use crate::log;
+use crate::{Error, ManifestSnapshots, Options, Selection, changes, requirements, write_manifest};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item};
+
+/// Version change applied by [`bump`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BumpLevel {
+    /// `1.2.3` becomes `2.0.0`, and `2.0.0-rc.1` becomes `2.0.0`.
+    Major,
+    /// `1.2.3` becomes `1.3.0`, and `1.3.0-rc.1` becomes `1.3.0`.
+    Minor,
+    /// `1.2.3` becomes `1.2.4`, and `1.2.4-rc.1` becomes `1.2.4`.
+    Patch,
+    /// `1.2.3-alpha.1` becomes `1.2.3-alpha.2`, and `1.2.3` becomes `1.2.4-alpha.1`.
+    Prerelease,
+    /// Sets this version.
+    Version(semver::Version),
+}
+
+impl std::fmt::Display for BumpLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Major => write!(f, "major"),
+            Self::Minor => write!(f, "minor"),
+            Self::Patch => write!(f, "patch"),
+            Self::Prerelease => write!(f, "prerelease"),
+            Self::Version(version) => write!(f, "{version}"),
+        }
+    }
+}
+
+impl std::str::FromStr for BumpLevel {
+    type Err = Error;
+
+    fn from_str(level: &str) -> Result<Self, Self::Err> {
+        match level.to_ascii_lowercase().as_str() {
+            "major" => Ok(Self::Major),
+            "minor" => Ok(Self::Minor),
+            "patch" => Ok(Self::Patch),
+            "prerelease" => Ok(Self::Prerelease),
+            _ => level.parse().map(Self::Version).map_err(|_| {
+                Error::InvalidOptions(format!(
+                    "unknown bump level {level:?} (expected major, minor, patch, prerelease or a version)"
+                ))
+            }),
+        }
+    }
+}
+
+impl BumpLevel {
+    /// Returns the version that replaces `version`.
+    ///
+    /// Major, minor and patch bumps drop pre-release and build metadata. As in cargo-release, a
+    /// pre-release that already is at the level is released rather than bumped again, so `patch`
+    /// turns `1.2.4-alpha.1` into `1.2.4` and `minor` turns `1.3.0-alpha.1` into `1.3.0`, but
+    /// `minor` turns `1.2.4-alpha.1` into `1.3.0`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the bumped pre-release identifier is invalid.
+    pub fn apply(&self, version: &semver::Version) -> crate::Result<semver::Version> {
+        let pre = !version.pre.is_empty();
+        let bumped = match self {
+            Self::Major if pre && version.minor == 0 && version.patch == 0 => {
+                semver::Version::new(version.major, 0, 0)
+            }
+            Self::Major => semver::Version::new(version.major + 1, 0, 0),
+            Self::Minor if pre && version.patch == 0 => {
+                semver::Version::new(version.major, version.minor, 0)
+            }
+            Self::Minor => semver::Version::new(version.major, version.minor + 1, 0),
+            Self::Patch if pre => semver::Version::new(version.major, version.minor, version.patch),
+            Self::Patch => semver::Version::new(version.major, version.minor, version.patch + 1),
+            Self::Prerelease if version.pre.is_empty() => semver::Version {
+                pre: semver::Prerelease::new("alpha.1")?,
+                ..semver::Version::new(version.major, version.minor, version.patch + 1)
+            },
+            Self::Prerelease => {
+                let pre = version.pre.as_str();
+                let (prefix, number) = pre.rsplit_once('.').unwrap_or(("", pre));
+                let pre = match (prefix, number.parse::<u64>()) {
+                    ("", Ok(number)) => (number + 1).to_string(),
+                    (prefix, Ok(number)) => format!("{prefix}.{}", number + 1),
+                    (_, Err(_)) => format!("{pre}.1"),
+                };
+                semver::Version {
+                    pre: semver::Prerelease::new(&pre)?,
+                    ..semver::Version::new(version.major, version.minor, version.patch)
+                }
+            }
+            Self::Version(version) => version.clone(),
+        };
+        Ok(bumped)
+    }
+}
+
+/// A package version changed by [`bump`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BumpedPackage {
+    /// Package name.
+    pub name: String,
+    /// Version before the bump.
+    pub from: semver::Version,
+    /// Version after the bump.
+    pub to: semver::Version,
+}
+
+/// Versions changed by [`bump`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BumpReport {
+    /// Bumped packages, sorted by name.
+    pub packages: Vec<BumpedPackage>,
+}
+
+impl std::fmt::Display for BumpReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.packages.is_empty() {
+            writeln!(f, "nothing to bump")?;
+        }
+        for package in &self.packages {
+            writeln!(f, "{}: {} -> {}", package.name, package.from, package.to)?;
+        }
+        Ok(())
+    }
+}
+
+/// Original text and edited document of a manifest.
+struct Manifest {
+    original: String,
+    document: DocumentMut,
+}
+
+impl Manifest {
+    async fn read(path: &Path) -> crate::Result<Self> {
+        let original = tokio::fs::read_to_string(path).await?;
+        let document = original
+            .parse::<DocumentMut>()
+            .map_err(|error| Error::ManifestParse {
+                path: path.to_path_buf(),
+                error,
+            })?;
+        Ok(Self { original, document })
+    }
+}
+
+/// Returns `true` if the package manifest uses `version.workspace = true`.
+fn inherits_version(manifest: &DocumentMut) -> bool {
+    manifest
+        .get("package")
+        .and_then(|package| package.get("version"))
+        .and_then(Item::as_table_like)
+        .and_then(|version| version.get("workspace"))
+        .and_then(Item::as_bool)
+        == Some(true)
+}
+
+/// Replaces the version string at `item` of the manifest at `path`, keeping its surrounding
+/// whitespace and comments.
+fn set_version(
+    path: &Path,
+    item: Option<&mut Item>,
+    version: &semver::Version,
+) -> crate::Result<()> {
+    let value = item
+        .and_then(Item::as_value_mut)
+        .filter(|value| value.is_str())
+        .ok_or_else(|| Error::ManifestEdit {
+            path: path.to_path_buf(),
+            message: "version is not a string".to_string(),
+        })?;
+    let decor = value.decor().clone();
+    *value = version.to_string().into();
+    *value.decor_mut() = decor;
+    Ok(())
+}
+
+/// Returns `workspace.package.version` of the workspace manifest at `path`.
+fn current_workspace_version(
+    path: &Path,
+    manifest: &DocumentMut,
+) -> crate::Result<semver::Version> {
+    let version = manifest
+        .get("workspace")
+        .and_then(|workspace| workspace.get("package"))
+        .and_then(|package| package.get("version"))
+        .and_then(Item::as_str)
+        .ok_or_else(|| Error::ManifestEdit {
+            path: path.to_path_buf(),
+            message: "workspace.package.version is missing".to_string(),
+        })?;
+    Ok(version.parse()?)
+}
+
+fn bumped(
+    name: &str,
+    version: &semver::Version,
+    level: &BumpLevel,
+) -> crate::Result<semver::Version> {
+    let bumped = level.apply(version)?;
+    if bumped < *version {
+        return Err(Error::VersionDecrease {
+            package: name.to_string(),
+            from: version.clone(),
+            to: bumped,
+        });
+    }
+    Ok(bumped)
+}
+
+/// Returns the names of the workspace members to bump.
+async fn select_packages(
+    metadata: &cargo_metadata::Metadata,
+    options: &Options,
+) -> crate::Result<BTreeSet<String>> {
+    // Requirements on bumped packages change, so their dependants are always bumped as well.
+    let changed = match &options.changed_since {
+        Some(git_ref) => Some(changes::changed_since(metadata, git_ref, true).await?),
+        None => None,
+    };
+    let selection = Selection::new(metadata, options, changed.as_ref())?;
+    Ok(metadata
+        .workspace_packages()
+        .iter()
+        .filter(|package| selection.skip_reason(&package.name).is_none())
+        .map(|package| package.name.to_string())
+        .collect())
+}
+
+/// Bumps the versions of selected workspace packages and rewrites the requirements on them.
+///
+/// Packages are selected by [`Options::include`] and [`Options::exclude`], and with
+/// [`Options::changed_since`], further limited to packages with files that changed since then and
+/// their transitive dependants, regardless of [`Options::include_dependants`]. Packages that
+/// inherit `version.workspace = true` share `workspace.package.version`, so bumping one of them
+/// bumps all of them.
+///
+/// Local dependency requirements on bumped packages, in member manifests and
+/// `[workspace.dependencies]`, are rewritten in [`Options::version_req_style`]. Without a style,
+/// requirements that still match are kept and others keep their operator, so `0.3` becomes `^0.4`
+/// rather than an exact pin. Versionless path dependencies are left for
+/// [`Options::resolve_versions`] to resolve when publishing. All manifests are rewritten or none
+/// are: a failed write restores the manifests written before it. With [`Options::dry_run`], the
+/// bump is computed without modifying manifests.
+///
+/// # Errors
+///
+/// Returns an error when the options, configuration, Cargo metadata or manifests are invalid, a
+/// version would decrease, a requirement cannot be written in the requirement style, or git fails
+/// to list changed files.
+pub async fn bump(options: Options, level: &BumpLevel) -> crate::Result<BumpReport> {
+    let options = options.with_workspace_config()?;
+    let metadata = cargo_metadata::MetadataCommand::new()
+        .manifest_path(options.manifest_path())
+        .no_deps()
+        .exec()?;
+    let selected = select_packages(&metadata, &options).await?;
+
+    // The workspace root may also be a package, so every manifest is edited at most once.
+    let workspace_path = PathBuf::from(metadata.workspace_root.join("Cargo.toml"));
+    let mut manifests = BTreeMap::new();
+    let members = metadata.workspace_packages();
+    let paths = std::iter::once(workspace_path.clone()).chain(
+        members
+            .iter()
+            .map(|package| package.manifest_path.clone().into()),
+    );
+    for path in paths {
+        let manifest = Manifest::read(&path).await?;
+        manifests.insert(path, manifest);
+    }
+    let workspace = |manifests: &BTreeMap<PathBuf, Manifest>| {
+        manifests
+            .get(&workspace_path)
+            .map(|manifest| manifest.document.clone())
+            .ok_or_else(|| not_read(&workspace_path))
+    };
+
+    let inherits = |package: &cargo_metadata::Package| {
+        manifests
+            .get(package.manifest_path.as_std_path())
+            .is_some_and(|manifest| inherits_version(&manifest.document))
+    };
+    let workspace_version = if members
+        .iter()
+        .any(|package| selected.contains(package.name.as_str()) && inherits(package))
+    {
+        let version = current_workspace_version(&workspace_path, &workspace(&manifests)?)?;
+        Some(bumped("workspace", &version, level)?)
+    } else {
+        None
+    };
+
+    // New versions by package directory, which local path dependencies point to.
+    let mut versions = HashMap::new();
+    let mut report = BumpReport::default();
+    for package in &members {
+        let to = match &workspace_version {
+            Some(to) if inherits(package) => to.clone(),
+            _ if !inherits(package) && selected.contains(package.name.as_str()) => {
+                bumped(&package.name, &package.version, level)?
+            }
+            _ => continue,
+        };
+        if let Some(dir) = package.manifest_path.parent() {
+            versions.insert(PathBuf::from(dir), (package.name.to_string(), to.clone()));
+        }
+        report.packages.push(BumpedPackage {
+            name: package.name.to_string(),
+            from: package.version.clone(),
+            to,
+        });
+    }
+    report.packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let workspace_document = workspace(&manifests)?;
+    for package in &members {
+        let manifest = manifests
+            .get_mut(package.manifest_path.as_std_path())
+            .ok_or_else(|| not_read(package.manifest_path.as_std_path()))?;
+        rewrite_member(
+            package,
+            &mut manifest.document,
+            &workspace_document,
+            &versions,
+            &options,
+        )?;
+    }
+
+    let manifest = manifests
+        .get_mut(&workspace_path)
+        .ok_or_else(|| not_read(&workspace_path))?;
+    rewrite_workspace(
+        &workspace_path,
+        &mut manifest.document,
+        workspace_version.as_ref(),
+        &versions,
+        &options,
+    )?;
+
+    let changed = manifests
+        .iter()
+        .filter_map(|(path, manifest)| {
+            let contents = manifest.document.to_string();
+            (contents != manifest.original).then_some((path.as_path(), manifest, contents))
+        })
+        .collect::<Vec<_>>();
+    for (path, ..) in &changed {
+        log::info!(options.log(), "updating {}", path.display());
+    }
+    if !options.dry_run {
+        write_manifests(&changed, options.log()).await?;
+    }
+    Ok(report)
+}
+
+/// Writes every changed manifest, or none of them.
+///
+/// When a write fails, the manifests written before it are restored, so the workspace is never
+/// left with some packages bumped and requirements on them outdated.
+async fn write_manifests(
+    changed: &[(&Path, &Manifest, String)],
+    log: log::Log,
+) -> crate::Result<()> {
+    let snapshots = ManifestSnapshots::default();
+    for (path, manifest, contents) in changed {
+        let written = match snapshots.record(path) {
+            Ok(()) => write_manifest(path, &manifest.original, contents).await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = written {
+            // Files that failed to restore were logged, so report the error that stopped writing.
+            let _ = snapshots.restore(log).await;
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// Reports a manifest that is missing from the manifests read by [`bump`].
+fn not_read(path: &Path) -> Error {
+    Error::ManifestEdit {
+        path: path.to_path_buf(),
+        message: "was not read".to_string(),
+    }
+}
+
+/// Writes the bumped version of `package` and its requirements on bumped local dependencies.
+fn rewrite_member(
+    package: &cargo_metadata::Package,
+    manifest: &mut DocumentMut,
+    workspace_manifest: &DocumentMut,
+    versions: &HashMap<PathBuf, (String, semver::Version)>,
+    options: &Options,
+) -> crate::Result<()> {
+    let dir = package.manifest_path.parent().map(PathBuf::from);
+    if !inherits_version(manifest)
+        && let Some((_, to)) = dir.and_then(|dir| versions.get(&dir))
+    {
+        let version = manifest
+            .get_mut("package")
+            .and_then(|package| package.get_mut("version"));
+        set_version(package.manifest_path.as_std_path(), version, to)?;
+    }
+
+    for dependency in &package.dependencies {
+        let Some((_, to)) = dependency
+            .path
+            .as_ref()
+            .and_then(|path| versions.get(&PathBuf::from(path)))
+        else {
+            continue;
+        };
+        let declaration = crate::dependency_declaration(
+            manifest,
+            workspace_manifest,
+            package.manifest_path.as_std_path(),
+            dependency,
+        )?;
+        // Inherited requirements are rewritten in `[workspace.dependencies]`.
+        if !declaration.has_version || declaration.inherits_workspace {
+            continue;
+        }
+        let requirement =
+            requirements::bumped_requirement(options.version_req_style, to, &dependency.req)
+                .map_err(|err| Error::Dependency {
+                    package: package.name.to_string(),
+                    dependency: dependency.name.clone(),
+                    source: Box::new(err),
+                })?;
+        if requirement != dependency.req {
+            crate::update_dependency_version(
+                manifest,
+                package.manifest_path.as_std_path(),
+                dependency,
+                &requirement,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the bumped `workspace.package.version` and requirements in `[workspace.dependencies]`.
+fn rewrite_workspace(
+    path: &Path,
+    manifest: &mut DocumentMut,
+    workspace_version: Option<&semver::Version>,
+    versions: &HashMap<PathBuf, (String, semver::Version)>,
+    options: &Options,
+) -> crate::Result<()> {
+    if let Some(to) = workspace_version {
+        let version = manifest
+            .get_mut("workspace")
+            .and_then(|workspace| workspace.get_mut("package"))
+            .and_then(|package| package.get_mut("version"));
+        set_version(path, version, to)?;
+    }
+
+    let versions = versions.values().cloned().collect::<HashMap<_, _>>();
+    crate::rewrite_workspace_dependencies(manifest, |package_name, dep_name, existing| {
+        match (existing, versions.get(package_name)) {
+            (Some(existing), Some(to)) => {
+                let existing = existing.parse::<semver::VersionReq>()?;
+                let requirement =
+                    requirements::bumped_requirement(options.version_req_style, to, &existing)
+                        .map_err(|err| Error::Dependency {
+                            package: "workspace".to_string(),
+                            dependency: dep_name.to_string(),
+                            source: Box::new(err),
+                        })?;
+                Ok((requirement != existing).then(|| requirement.to_string()))
+            }
+            _ => Ok(None),
+        }
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BumpLevel;
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    #[test]
+    fn applies_bump_levels() {
+        let apply = |level: &str, version: &str| {
+            let level = level.parse::<BumpLevel>().expect("bump level must parse");
+            let version = version.parse().expect("version must parse");
+            level
+                .apply(&version)
+                .expect("version must bump")
+                .to_string()
+        };
+        sim_assert_eq!(apply("major", "1.2.3"), "2.0.0");
+        sim_assert_eq!(apply("minor", "1.2.3"), "1.3.0");
+        sim_assert_eq!(apply("patch", "1.2.3"), "1.2.4");
+        // Pre-releases of the level are released, others are bumped past.
+        sim_assert_eq!(apply("major", "2.0.0-rc.1"), "2.0.0");
+        sim_assert_eq!(apply("major", "1.2.3-rc.1"), "2.0.0");
+        sim_assert_eq!(apply("minor", "1.3.0-alpha.1"), "1.3.0");
+        sim_assert_eq!(apply("minor", "1.2.4-alpha.1"), "1.3.0");
+        sim_assert_eq!(apply("patch", "1.2.4-alpha.1"), "1.2.4");
+        sim_assert_eq!(apply("patch", "1.2.4-alpha.1+build.5"), "1.2.4");
+        sim_assert_eq!(apply("prerelease", "1.2.3"), "1.2.4-alpha.1");
+        sim_assert_eq!(apply("prerelease", "1.2.4-alpha.9"), "1.2.4-alpha.10");
+        sim_assert_eq!(apply("prerelease", "1.2.4-rc"), "1.2.4-rc.1");
+        sim_assert_eq!(apply("prerelease", "1.2.4-3"), "1.2.4-4");
+        sim_assert_eq!(apply("2.0.0-beta.1", "1.2.3"), "2.0.0-beta.1");
+        sim_assert_eq!(
+            "huge".parse::<BumpLevel>().map_err(|err| err.to_string()),
+            Err(
+                "unknown bump level \"huge\" (expected major, minor, patch, prerelease or a version)"
+                    .to_string()
+            )
+        );
+    }
+}
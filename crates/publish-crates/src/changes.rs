@@ -0,0 +1,256 @@
+//! Workspace packages changed since a git revision.
+
+use crate::Error;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Runs git in `dir` and returns its standard output.
+async fn git(dir: &Path, args: &[&str]) -> crate::Result<Vec<u8>> {
+    let output = async_process::Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(Error::Git {
+            args: args.iter().map(ToString::to_string).collect(),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(output.stdout)
+}
+
+/// Splits NUL-separated git output into paths relative to `root`.
+fn paths(root: &Path, output: &[u8]) -> Vec<PathBuf> {
+    output
+        .split(|byte| *byte == 0)
+        .filter(|path| !path.is_empty())
+        .map(|path| root.join(String::from_utf8_lossy(path).as_ref()))
+        .collect()
+}
+
+/// Returns the files in the working tree of `dir` that differ from `git_ref`, including untracked
+/// files that are not ignored.
+async fn changed_files(dir: &Path, git_ref: &str) -> crate::Result<Vec<PathBuf>> {
+    let root = git(dir, &["rev-parse", "--show-toplevel"]).await?;
+    let root = PathBuf::from(String::from_utf8_lossy(&root).trim());
+
+    // The user-supplied revision must never be read as an option such as `--output`.
+    let diff = git(
+        &root,
+        &[
+            "diff",
+            "--name-only",
+            "-z",
+            "--end-of-options",
+            git_ref,
+            "--",
+        ],
+    )
+    .await
+    .map_err(|err| Error::ChangedSince {
+        git_ref: git_ref.to_string(),
+        source: Box::new(err),
+    })?;
+    let untracked = git(&root, &["ls-files", "--others", "--exclude-standard", "-z"]).await?;
+
+    let mut files = paths(&root, &diff);
+    files.extend(paths(&root, &untracked));
+    Ok(files)
+}
+
+/// Returns the names of packages in `package_dirs` that contain one of `files`.
+///
+/// Each file belongs to the package with the most specific directory, so changes in a nested
+/// package do not mark the enclosing package as changed.
+fn changed_packages(
+    package_dirs: &HashMap<String, PathBuf>,
+    files: &[PathBuf],
+) -> BTreeSet<String> {
+    // Git reports paths below the canonical repository root.
+    let package_dirs = package_dirs
+        .iter()
+        .map(|(name, dir)| (name, dir.canonicalize().unwrap_or_else(|_| dir.clone())))
+        .collect::<Vec<_>>();
+    files
+        .iter()
+        .filter_map(|file| {
+            package_dirs
+                .iter()
+                .filter(|(_, dir)| file.starts_with(dir))
+                .max_by_key(|(_, dir)| dir.components().count())
+                .map(|(name, _)| (*name).clone())
+        })
+        .collect()
+}
+
+/// Returns whether `item` is a table that inherits a value from the workspace, directly or in a
+/// nested table, such as `version.workspace = true` or `serde = { workspace = true }`.
+fn inherits(item: &toml_edit::Item) -> bool {
+    item.as_table_like().is_some_and(|table| {
+        table.iter().any(|(key, item)| {
+            (key == "workspace" && item.as_bool() == Some(true)) || inherits(item)
+        })
+    })
+}
+
+/// Returns whether the manifest at `path` inherits a field, dependency or lint from the workspace.
+///
+/// Manifests that cannot be read are assumed to inherit, so their packages are never missed.
+fn inherits_from_workspace(path: &Path) -> bool {
+    let Ok(Ok(manifest)) =
+        std::fs::read_to_string(path).map(|manifest| manifest.parse::<toml_edit::DocumentMut>())
+    else {
+        return true;
+    };
+    manifest
+        .iter()
+        .any(|(key, item)| key != "workspace" && inherits(item))
+}
+
+/// Returns the names of `members` affected by changes to the workspace-level `files`.
+///
+/// A changed `Cargo.lock` affects every member, and a changed workspace manifest affects the
+/// members that inherit from it, such as from `[workspace.package]` or `[workspace.dependencies]`.
+fn workspace_changes(
+    workspace_root: &Path,
+    members: &[&cargo_metadata::Package],
+    files: &[PathBuf],
+) -> BTreeSet<String> {
+    // Git reports paths below the canonical repository root.
+    let workspace_root = workspace_root
+        .canonicalize()
+        .unwrap_or_else(|_| workspace_root.to_path_buf());
+    let changed = |name: &str| files.contains(&workspace_root.join(name));
+    let lockfile = changed("Cargo.lock");
+    if !lockfile && !changed("Cargo.toml") {
+        return BTreeSet::new();
+    }
+    members
+        .iter()
+        .filter(|package| lockfile || inherits_from_workspace(package.manifest_path.as_std_path()))
+        .map(|package| package.name.to_string())
+        .collect()
+}
+
+/// Extends `selected` with every package that transitively depends on one of them.
+///
+/// `dependants` maps a package name to the names of the packages that depend on it.
+fn with_dependants(
+    selected: BTreeSet<String>,
+    dependants: &HashMap<String, HashSet<String>>,
+) -> BTreeSet<String> {
+    let mut queue = selected.iter().cloned().collect::<Vec<_>>();
+    let mut selected = selected;
+    while let Some(name) = queue.pop() {
+        for dependant in dependants.get(&name).into_iter().flatten() {
+            if selected.insert(dependant.clone()) {
+                queue.push(dependant.clone());
+            }
+        }
+    }
+    selected
+}
+
+/// Returns the workspace members with files that changed since `git_ref`.
+///
+/// Changes to the workspace manifest and `Cargo.lock` count as changes of the members they
+/// affect, see [`workspace_changes`]. With `dependants`, every member that transitively depends on a changed member through a local
+/// path dependency of any kind is included as well.
+pub(crate) async fn changed_since(
+    metadata: &cargo_metadata::Metadata,
+    git_ref: &str,
+    dependants: bool,
+) -> crate::Result<BTreeSet<String>> {
+    let members = metadata.workspace_packages();
+    let package_dirs = members
+        .iter()
+        .filter_map(|package| {
+            let dir = package.manifest_path.parent()?;
+            Some((package.name.to_string(), PathBuf::from(dir)))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let files = changed_files(metadata.workspace_root.as_std_path(), git_ref).await?;
+    let mut changed = changed_packages(&package_dirs, &files);
+    changed.extend(workspace_changes(
+        metadata.workspace_root.as_std_path(),
+        &members,
+        &files,
+    ));
+    if !dependants {
+        return Ok(changed);
+    }
+
+    let mut dependants: HashMap<String, HashSet<String>> = HashMap::new();
+    for package in &members {
+        for dependency in &package.dependencies {
+            let Some(path) = dependency.path.as_ref().map(PathBuf::from) else {
+                continue;
+            };
+            if let Some((name, _)) = package_dirs.iter().find(|(_, dir)| **dir == path) {
+                dependants
+                    .entry(name.clone())
+                    .or_default()
+                    .insert(package.name.to_string());
+            }
+        }
+    }
+    Ok(with_dependants(changed, &dependants))
+}
+
+#[cfg(test)]
+mod tests {
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use std::collections::{BTreeSet, HashMap, HashSet};
+    use std::path::PathBuf;
+
+    #[test]
+    fn maps_files_to_most_specific_package_and_dependants() {
+        let package_dirs = HashMap::from([
+            ("app".to_string(), PathBuf::from("/repo")),
+            ("core".to_string(), PathBuf::from("/repo/crates/core")),
+            ("cli".to_string(), PathBuf::from("/repo/crates/cli")),
+        ]);
+        let files = [
+            PathBuf::from("/repo/crates/core/src/lib.rs"),
+            PathBuf::from("/repo/crates/core/Cargo.toml"),
+        ];
+        let changed = super::changed_packages(&package_dirs, &files);
+        sim_assert_eq!(changed, BTreeSet::from(["core".to_string()]));
+
+        let dependants = HashMap::from([
+            ("core".to_string(), HashSet::from(["cli".to_string()])),
+            ("cli".to_string(), HashSet::from(["app".to_string()])),
+        ]);
+        sim_assert_eq!(
+            super::with_dependants(changed, &dependants),
+            BTreeSet::from(["app".to_string(), "cli".to_string(), "core".to_string()])
+        );
+    }
+
+    #[test]
+    fn detects_members_inheriting_from_the_workspace() {
+        let temp = tempfile::tempdir().expect("temporary directory must be created");
+        let manifest = |contents: &str| {
+            let path = temp.path().join("Cargo.toml");
+            std::fs::write(&path, contents).expect("manifest must be written");
+            super::inherits_from_workspace(&path)
+        };
+        assert!(manifest(
+            "[package]\nname = \"foo\"\nversion.workspace = true\n"
+        ));
+        assert!(manifest(
+            "[package]\nname = \"foo\"\n\n[dependencies]\nserde = { workspace = true }\n"
+        ));
+        assert!(manifest(
+            "[package]\nname = \"foo\"\n\n[target.'cfg(unix)'.dependencies.libc]\nworkspace = true\n"
+        ));
+        assert!(!manifest(
+            "[package]\nname = \"foo\"\nversion = \"1.2.3\"\n\n[workspace]\nresolver = \"2\"\n"
+        ));
+        assert!(!manifest(
+            "[package]\nname = \"foo\"\n\n[dependencies]\nserde = { workspace = false }\n"
+        ));
+    }
+}
@@ -0,0 +1,589 @@
+//! Classification of failed `cargo publish` attempts.
+
+/// How publishing proceeds after a failed `cargo publish` attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    /// The attempt may succeed when retried.
+    Retryable,
+    /// Retrying cannot fix the failure.
+    Fatal,
+    /// The version is already on the registry, so the package counts as published.
+    AlreadyPublished,
+}
+
+impl std::fmt::Display for FailureCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let category = match self {
+            Self::Retryable => "retryable",
+            Self::Fatal => "fatal",
+            Self::AlreadyPublished => "already published",
+        };
+        write!(f, "{category}")
+    }
+}
+
+/// A `cargo publish` or registry failure recognized by its message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum KnownFailure {
+    /// The version already exists on the registry.
+    AlreadyPublished,
+    /// The registry already has the version with a different checksum.
+    ChecksumMismatch,
+    /// The crate name is reserved, or owned by someone else.
+    NameUnavailable,
+    /// The manifest lacks metadata the registry requires, such as `license` or `description`.
+    MissingMetadata,
+    /// A dependency has no version requirement.
+    MissingVersionRequirement,
+    /// The registry token is missing or rejected.
+    InvalidToken,
+    /// The working tree has uncommitted changes.
+    DirtyWorkingTree,
+    /// A dependency is not in the registry index yet.
+    DependencyNotIndexed,
+    /// The packaged crate does not build.
+    VerificationFailed,
+    /// The registry cannot be reached, for example because DNS lookup or the connection failed.
+    Network,
+}
+
+impl KnownFailure {
+    /// Returns how publishing proceeds after this failure.
+    #[must_use]
+    pub fn category(self) -> FailureCategory {
+        match self {
+            Self::AlreadyPublished => FailureCategory::AlreadyPublished,
+            Self::DependencyNotIndexed | Self::Network => FailureCategory::Retryable,
+            Self::ChecksumMismatch
+            | Self::NameUnavailable
+            | Self::MissingMetadata
+            | Self::MissingVersionRequirement
+            | Self::InvalidToken
+            | Self::DirtyWorkingTree
+            | Self::VerificationFailed => FailureCategory::Fatal,
+        }
+    }
+
+    /// Returns how the user can fix the failure, if known.
+    pub(crate) fn suggestion(self) -> Option<&'static str> {
+        let suggestion = match self {
+            Self::ChecksumMismatch => "published versions cannot be replaced; bump the version",
+            Self::NameUnavailable => {
+                "rename the package, or ask an owner of the crate to invite you"
+            }
+            Self::MissingMetadata => {
+                "set `description` and `license` or `license-file` in the package manifest"
+            }
+            Self::MissingVersionRequirement => {
+                "to automatically resolve versions of local workspace members, use '--resolve-versions'"
+            }
+            Self::InvalidToken => {
+                "check the registry token, for example `--registry-token` or `CARGO_REGISTRY_TOKEN`"
+            }
+            Self::DirtyWorkingTree => "commit the changes, or pass '--allow-dirty' after `--`",
+            Self::VerificationFailed => {
+                "fix the build of the packaged crate, or skip verification with '--no-verify'"
+            }
+            Self::AlreadyPublished | Self::DependencyNotIndexed | Self::Network => return None,
+        };
+        Some(suggestion)
+    }
+}
+
+impl std::fmt::Display for KnownFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let failure = match self {
+            Self::AlreadyPublished => "already published",
+            Self::ChecksumMismatch => "checksum mismatch",
+            Self::NameUnavailable => "name unavailable",
+            Self::MissingMetadata => "missing metadata",
+            Self::MissingVersionRequirement => "missing version requirement",
+            Self::InvalidToken => "invalid token",
+            Self::DirtyWorkingTree => "dirty working tree",
+            Self::DependencyNotIndexed => "dependency not indexed",
+            Self::VerificationFailed => "verification failed",
+            Self::Network => "network error",
+        };
+        write!(f, "{failure}")
+    }
+}
+
+/// Classification of publishing errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PublishError {
+    /// An error without a recognized signature or HTTP status code.
+    Unknown,
+    /// A transient HTTP error that may succeed when retried.
+    Retryable(http::StatusCode),
+    /// An HTTP error that should not be retried.
+    Fatal(http::StatusCode),
+    /// A failure recognized by its message.
+    Known(KnownFailure),
+}
+
+impl PublishError {
+    /// Returns the recognized HTTP status code, if one was found.
+    #[must_use]
+    pub fn code(&self) -> Option<&http::StatusCode> {
+        match self {
+            Self::Unknown | Self::Known(_) => None,
+            Self::Retryable(code) | Self::Fatal(code) => Some(code),
+        }
+    }
+
+    /// Returns the recognized failure, if the message matched a known signature.
+    #[must_use]
+    pub fn failure(&self) -> Option<KnownFailure> {
+        match self {
+            Self::Known(failure) => Some(*failure),
+            _ => None,
+        }
+    }
+
+    /// Returns how publishing proceeds after this error.
+    ///
+    /// Unknown errors are retried.
+    #[must_use]
+    pub fn category(&self) -> FailureCategory {
+        match self {
+            Self::Unknown | Self::Retryable(_) => FailureCategory::Retryable,
+            Self::Fatal(_) => FailureCategory::Fatal,
+            Self::Known(failure) => failure.category(),
+        }
+    }
+}
+
+impl std::fmt::Display for PublishError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Unknown => write!(f, "unknown"),
+            Self::Retryable(code) => write!(f, "retryable ({code})"),
+            Self::Fatal(code) => write!(f, "fatal ({code})"),
+            Self::Known(failure) => write!(f, "{} ({failure})", failure.category()),
+        }
+    }
+}
+
+impl serde::Serialize for PublishError {
+    /// Serializes as `{"kind": "retryable", "status": 429}` for HTTP errors and as
+    /// `{"kind": "fatal", "failure": "invalid_token"}` for known failures.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("PublishError", 3)?;
+        match self {
+            Self::Unknown => state.serialize_field("kind", "unknown")?,
+            _ => state.serialize_field("kind", &self.category())?,
+        }
+        match self.code() {
+            Some(code) => state.serialize_field("status", &code.as_u16())?,
+            None => state.skip_field("status")?,
+        }
+        match self.failure() {
+            Some(failure) => state.serialize_field("failure", &failure)?,
+            None => state.skip_field("failure")?,
+        }
+        state.end()
+    }
+}
+
+/// Message fragments that identify a [`KnownFailure`].
+struct Signature {
+    /// Lowercase fragments that must all appear in the message.
+    fragments: &'static [&'static str],
+    failure: KnownFailure,
+}
+
+/// Known failures of `cargo publish` and the registries, in order of precedence.
+///
+/// Checksum mismatches precede [`KnownFailure::AlreadyPublished`] because crates.io reports them
+/// as an existing version, and unindexed dependencies precede
+/// [`KnownFailure::VerificationFailed`] because Cargo resolves dependencies while verifying.
+const SIGNATURES: &[Signature] = &[
+    Signature {
+        fragments: &["failed to verify the checksum of "],
+        failure: KnownFailure::ChecksumMismatch,
+    },
+    Signature {
+        fragments: &["already uploaded", "different checksum"],
+        failure: KnownFailure::ChecksumMismatch,
+    },
+    // Cargo reports `crates.io index` or `` `<name>` index `` depending on the registry.
+    Signature {
+        fragments: &["already exists on ", " index"],
+        failure: KnownFailure::AlreadyPublished,
+    },
+    Signature {
+        fragments: &["is already uploaded"],
+        failure: KnownFailure::AlreadyPublished,
+    },
+    Signature {
+        fragments: &["reserved name"],
+        failure: KnownFailure::NameUnavailable,
+    },
+    Signature {
+        fragments: &["crate name", "is reserved"],
+        failure: KnownFailure::NameUnavailable,
+    },
+    Signature {
+        fragments: &["don't seem to be an owner"],
+        failure: KnownFailure::NameUnavailable,
+    },
+    Signature {
+        fragments: &["crate was previously named"],
+        failure: KnownFailure::NameUnavailable,
+    },
+    Signature {
+        fragments: &["missing or empty metadata fields"],
+        failure: KnownFailure::MissingMetadata,
+    },
+    Signature {
+        fragments: &["all dependencies must have a version"],
+        failure: KnownFailure::MissingVersionRequirement,
+    },
+    Signature {
+        fragments: &["no token found"],
+        failure: KnownFailure::InvalidToken,
+    },
+    Signature {
+        fragments: &["authentication failed"],
+        failure: KnownFailure::InvalidToken,
+    },
+    Signature {
+        fragments: &["invalid upload token"],
+        failure: KnownFailure::InvalidToken,
+    },
+    Signature {
+        fragments: &["token is invalid"],
+        failure: KnownFailure::InvalidToken,
+    },
+    Signature {
+        fragments: &["contain changes that were not yet committed"],
+        failure: KnownFailure::DirtyWorkingTree,
+    },
+    Signature {
+        fragments: &["no matching package named"],
+        failure: KnownFailure::DependencyNotIndexed,
+    },
+    Signature {
+        fragments: &["failed to select a version for the requirement"],
+        failure: KnownFailure::DependencyNotIndexed,
+    },
+    Signature {
+        fragments: &["failed to verify package tarball"],
+        failure: KnownFailure::VerificationFailed,
+    },
+    Signature {
+        fragments: &["failed to lookup address information"],
+        failure: KnownFailure::Network,
+    },
+    Signature {
+        fragments: &["dns error"],
+        failure: KnownFailure::Network,
+    },
+    Signature {
+        fragments: &["could not resolve host"],
+        failure: KnownFailure::Network,
+    },
+    Signature {
+        fragments: &["connection reset"],
+        failure: KnownFailure::Network,
+    },
+    Signature {
+        fragments: &["connection refused"],
+        failure: KnownFailure::Network,
+    },
+    // Scoped to connection timeouts, so a verification build that times out stays fatal.
+    Signature {
+        fragments: &["operation timed out"],
+        failure: KnownFailure::Network,
+    },
+    Signature {
+        fragments: &["[28] timeout was reached"],
+        failure: KnownFailure::Network,
+    },
+    Signature {
+        fragments: &["spurious network error"],
+        failure: KnownFailure::Network,
+    },
+];
+
+/// Classifies a `cargo publish` error from its output.
+///
+/// Classification:
+///
+/// - [`PublishError::Known`] when the output matches a known failure signature.
+/// - [`PublishError::Retryable`] for temporary or intermittent HTTP errors.
+/// - [`PublishError::Fatal`] for permanent HTTP errors such as missing permissions.
+/// - [`PublishError::Unknown`] when neither a signature nor status text is present.
+///
+/// HTTP errors are recognized by status text of the form `<code> <canonical_reason>`.
+pub(crate) fn classify_publish_error(text: &str) -> PublishError {
+    let lowercase = text.to_lowercase();
+    if let Some(signature) = SIGNATURES.iter().find(|signature| {
+        signature
+            .fragments
+            .iter()
+            .all(|fragment| lowercase.contains(fragment))
+    }) {
+        return PublishError::Known(signature.failure);
+    }
+
+    for code_num in 100u16..=599 {
+        let Ok(code) = http::StatusCode::from_u16(code_num) else {
+            continue;
+        };
+        let Some(reason) = code.canonical_reason() else {
+            continue;
+        };
+
+        let needle = format!("{} {}", code.as_str(), reason);
+        if text.contains(&needle) {
+            if code.is_redirection()
+                || code.is_server_error()
+                || code == http::StatusCode::NOT_FOUND
+                || code == http::StatusCode::REQUEST_TIMEOUT
+                || code == http::StatusCode::CONFLICT
+                || code == http::StatusCode::GONE
+                || code == http::StatusCode::PRECONDITION_FAILED
+                || code == http::StatusCode::RANGE_NOT_SATISFIABLE
+                || code == http::StatusCode::EXPECTATION_FAILED
+                || code == http::StatusCode::MISDIRECTED_REQUEST
+                || code == http::StatusCode::UNPROCESSABLE_ENTITY
+                || code == http::StatusCode::LOCKED
+                || code == http::StatusCode::FAILED_DEPENDENCY
+                || code == http::StatusCode::TOO_EARLY
+                || code == http::StatusCode::UPGRADE_REQUIRED
+                || code == http::StatusCode::PRECONDITION_REQUIRED
+                || code == http::StatusCode::TOO_MANY_REQUESTS
+                || code == http::StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS
+            {
+                return PublishError::Retryable(code);
+            }
+            return PublishError::Fatal(code);
+        }
+    }
+
+    PublishError::Unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FailureCategory, KnownFailure, PublishError, classify_publish_error};
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    #[test]
+    fn classifies_retryable_fatal_and_unknown_publish_errors() {
+        let cases = [
+            (
+                "the remote server responded with 429 Too Many Requests",
+                PublishError::Retryable(http::StatusCode::TOO_MANY_REQUESTS),
+            ),
+            (
+                "the remote server responded with 500 Internal Server Error",
+                PublishError::Retryable(http::StatusCode::INTERNAL_SERVER_ERROR),
+            ),
+            (
+                "the remote server responded with 403 Forbidden",
+                PublishError::Fatal(http::StatusCode::FORBIDDEN),
+            ),
+            (
+                "the remote server responded with an unknown error",
+                PublishError::Unknown,
+            ),
+        ];
+
+        for (message, expected) in cases {
+            let error = classify_publish_error(message);
+            sim_assert_eq!(error, expected);
+            sim_assert_eq!(error.code(), expected.code());
+        }
+    }
+
+    fn assert_classified(cases: &[(&str, KnownFailure)]) {
+        for &(message, expected) in cases {
+            sim_assert_eq!(
+                classify_publish_error(message),
+                PublishError::Known(expected),
+                "{message}"
+            );
+        }
+    }
+
+    #[test]
+    fn classifies_already_published_and_fatal_signatures() {
+        assert_classified(&[
+            (
+                "error: crate foo@1.2.3 already exists on crates.io index",
+                KnownFailure::AlreadyPublished,
+            ),
+            (
+                "error: crate foo@1.2.3 already exists on `private` index",
+                KnownFailure::AlreadyPublished,
+            ),
+            (
+                "the remote server responded with an error (status 400 Bad Request): crate version `1.2.3` is already uploaded",
+                KnownFailure::AlreadyPublished,
+            ),
+            (
+                "the remote server responded with an error: crate version `1.2.3` is already uploaded with a different checksum",
+                KnownFailure::ChecksumMismatch,
+            ),
+            (
+                "error: failed to verify the checksum of `foo v1.2.3 (registry `private`)`",
+                KnownFailure::ChecksumMismatch,
+            ),
+            (
+                "the remote server responded with an error (status 400 Bad Request): cannot upload crate with a reserved name",
+                KnownFailure::NameUnavailable,
+            ),
+            (
+                "error: the crate name `std` is reserved",
+                KnownFailure::NameUnavailable,
+            ),
+            (
+                "the remote server responded with an error (status 403 Forbidden): this crate exists but you don't seem to be an owner. If you believe this is a mistake, perhaps you need to accept an invitation to be an owner before publishing.",
+                KnownFailure::NameUnavailable,
+            ),
+            (
+                "the remote server responded with an error (status 400 Bad Request): crate was previously named `Foo`",
+                KnownFailure::NameUnavailable,
+            ),
+            (
+                "the remote server responded with an error (status 400 Bad Request): missing or empty metadata fields: description, license. Please see https://doc.rust-lang.org/cargo/reference/manifest.html for more information on configuring these fields",
+                KnownFailure::MissingMetadata,
+            ),
+            (
+                "error: all dependencies must have a version requirement specified when publishing.\ndependency `bar` does not specify a version",
+                KnownFailure::MissingVersionRequirement,
+            ),
+            (
+                "error: no token found, please run `cargo login`",
+                KnownFailure::InvalidToken,
+            ),
+            (
+                "the remote server responded with an error (status 403 Forbidden): authentication failed",
+                KnownFailure::InvalidToken,
+            ),
+            (
+                "the remote server responded with an error: invalid upload token",
+                KnownFailure::InvalidToken,
+            ),
+            (
+                "error: the token is invalid or has expired",
+                KnownFailure::InvalidToken,
+            ),
+            (
+                "error: 2 files in the working directory contain changes that were not yet committed into git:\n\nsrc/lib.rs\n\nto proceed despite this and include the uncommitted changes, pass the `--allow-dirty` flag",
+                KnownFailure::DirtyWorkingTree,
+            ),
+            (
+                "error[E0425]: cannot find value `x` in this scope\nerror: failed to verify package tarball",
+                KnownFailure::VerificationFailed,
+            ),
+            (
+                "error: failed to verify package tarball\n\nCaused by:\n  failed to run custom build command for `foo v1.2.3`\n  --- stderr\n  download of bindings timed out",
+                KnownFailure::VerificationFailed,
+            ),
+        ]);
+    }
+
+    #[test]
+    fn classifies_retryable_signatures() {
+        assert_classified(&[
+            (
+                "error: failed to prepare local package for uploading\n\nCaused by:\n  no matching package named `bar` found",
+                KnownFailure::DependencyNotIndexed,
+            ),
+            (
+                "error: failed to verify package tarball\n\nCaused by:\n  failed to select a version for the requirement `bar = \"^1.2.3\"`",
+                KnownFailure::DependencyNotIndexed,
+            ),
+            (
+                "error: failed to get `bar` as a dependency\n\nCaused by:\n  [6] Couldn't resolve host name (Could not resolve host: index.crates.io)",
+                KnownFailure::Network,
+            ),
+            (
+                "error sending request: error trying to connect: dns error: failed to lookup address information: Name or service not known",
+                KnownFailure::Network,
+            ),
+            (
+                "error: failed to publish to registry at https://crates.io\n\nCaused by:\n  [56] Failure when receiving data from the peer (Recv failure: Connection reset by peer)",
+                KnownFailure::Network,
+            ),
+            (
+                "error trying to connect: tcp connect error: Connection refused (os error 111)",
+                KnownFailure::Network,
+            ),
+            (
+                "[28] Timeout was reached (Operation timed out after 30000 milliseconds with 0 bytes received)",
+                KnownFailure::Network,
+            ),
+            (
+                "error: failed to get `bar` as a dependency\n\nCaused by:\n  [28] Timeout was reached",
+                KnownFailure::Network,
+            ),
+            (
+                "error sending request: error trying to connect: operation timed out",
+                KnownFailure::Network,
+            ),
+            (
+                "warning: spurious network error (3 tries remaining): [7] Couldn't connect to server",
+                KnownFailure::Network,
+            ),
+        ]);
+    }
+
+    #[test]
+    fn does_not_classify_transient_checksum_errors_as_mismatches() {
+        sim_assert_eq!(
+            classify_publish_error(
+                "error: unexpected response: checksum header does not match the body, try again later"
+            ),
+            PublishError::Unknown
+        );
+    }
+
+    #[test]
+    fn categorizes_publish_errors() {
+        let cases = [
+            (PublishError::Unknown, FailureCategory::Retryable),
+            (
+                PublishError::Retryable(http::StatusCode::BAD_GATEWAY),
+                FailureCategory::Retryable,
+            ),
+            (
+                PublishError::Fatal(http::StatusCode::UNAUTHORIZED),
+                FailureCategory::Fatal,
+            ),
+            (
+                PublishError::Known(KnownFailure::Network),
+                FailureCategory::Retryable,
+            ),
+            (
+                PublishError::Known(KnownFailure::DependencyNotIndexed),
+                FailureCategory::Retryable,
+            ),
+            (
+                PublishError::Known(KnownFailure::InvalidToken),
+                FailureCategory::Fatal,
+            ),
+            (
+                PublishError::Known(KnownFailure::AlreadyPublished),
+                FailureCategory::AlreadyPublished,
+            ),
+        ];
+        for (error, expected) in cases {
+            sim_assert_eq!(error.category(), expected, "{error}");
+        }
+
+        let error = PublishError::Known(KnownFailure::InvalidToken);
+        sim_assert_eq!(error.to_string(), "fatal (invalid token)");
+        sim_assert_eq!(
+            serde_json::to_value(error).expect("error must serialize"),
+            serde_json::json!({"kind": "fatal", "failure": "invalid_token"})
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Release policy read from `[workspace.metadata.publish-crates]` and
+//! `[package.metadata.publish-crates]`.
+
+use crate::Options;
+use serde::{Deserialize, Deserializer};
+use std::time::Duration;
+
+/// Key of the publish-crates table in Cargo's `metadata` tables.
+const KEY: &str = "publish-crates";
+
+/// Deserializes an optional value from its string representation.
+fn from_str<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| value.parse().map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+/// Deserializes an optional human-readable duration, such as `30s` or `1m 30s`.
+fn duration<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(from_str::<D, humantime::Duration>(deserializer)?.map(Into::into))
+}
+
+/// Deserializes the publish-crates table of a Cargo `metadata` value, if present.
+fn parse<T>(metadata: &serde_json::Value) -> Result<T, serde_json::Error>
+where
+    T: for<'de> Deserialize<'de> + Default,
+{
+    match metadata.get(KEY) {
+        None | Some(serde_json::Value::Null) => Ok(T::default()),
+        Some(value) => T::deserialize(value),
+    }
+}
+
+/// Workspace-wide defaults for [`Options`] from `[workspace.metadata.publish-crates]`.
+///
+/// Settings passed on the command line, in `PUBLISH_CRATES_*` variables or as action inputs take
+/// precedence, including flags set to `false`. The retry policy is not configurable here.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct WorkspaceConfig {
+    #[serde(deserialize_with = "duration")]
+    publish_delay: Option<Duration>,
+    no_verify: Option<bool>,
+    resolve_versions: Option<bool>,
+    #[serde(deserialize_with = "from_str")]
+    version_req_style: Option<crate::VersionReqStyle>,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    include_dependencies: Option<bool>,
+    include_dependants: Option<bool>,
+    max_retries: Option<usize>,
+    concurrency_limit: Option<usize>,
+    extra_args: Vec<String>,
+    restore_manifests: Option<bool>,
+}
+
+impl WorkspaceConfig {
+    /// Reads the configuration of the workspace described by `metadata`.
+    pub(crate) fn from_metadata(metadata: &cargo_metadata::Metadata) -> crate::Result<Self> {
+        parse(&metadata.workspace_metadata).map_err(crate::Error::WorkspaceConfig)
+    }
+
+    /// Fills the settings that `options` leaves unset.
+    ///
+    /// Configured cargo arguments precede the arguments in `options`.
+    pub(crate) fn apply(self, options: &mut Options) {
+        options.publish_delay = options.publish_delay.or(self.publish_delay);
+        options.no_verify = options.no_verify.or(self.no_verify);
+        options.resolve_versions = options.resolve_versions.or(self.resolve_versions);
+        options.version_req_style = options.version_req_style.or(self.version_req_style);
+        options.include = options.include.take().or(self.include);
+        options.exclude = options.exclude.take().or(self.exclude);
+        options.include_dependencies = options.include_dependencies.or(self.include_dependencies);
+        options.include_dependants = options.include_dependants.or(self.include_dependants);
+        options.max_retries = options.max_retries.or(self.max_retries);
+        options.concurrency_limit = options.concurrency_limit.or(self.concurrency_limit);
+        options.restore_manifests = options.restore_manifests.or(self.restore_manifests);
+        if !self.extra_args.is_empty() {
+            let extra_args = std::mem::take(&mut options.extra_args);
+            options.extra_args = self.extra_args.into_iter().chain(extra_args).collect();
+        }
+    }
+}
+
+/// Overrides for a single package from `[package.metadata.publish-crates]`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct PackageConfig {
+    /// Never publish the package.
+    pub(crate) skip: bool,
+    /// Delay after the package becomes available, instead of [`Options::publish_delay`].
+    #[serde(deserialize_with = "duration")]
+    pub(crate) publish_delay: Option<Duration>,
+    /// Arguments appended to [`Options::extra_args`] for this package.
+    pub(crate) extra_args: Vec<String>,
+    /// Registry to publish to, unless the cargo arguments select one.
+    ///
+    /// Must be allowed by `package.publish`.
+    pub(crate) registry: Option<String>,
+    /// Passes `--no-verify` to `cargo publish`, instead of [`Options::no_verify`].
+    pub(crate) no_verify: Option<bool>,
+    /// Features enabled when `cargo publish` verifies the package.
+    pub(crate) features: Vec<String>,
+    /// Maximum retries for this package, instead of [`Options::max_retries`].
+    pub(crate) max_retries: Option<usize>,
+}
+
+impl PackageConfig {
+    /// Reads the configuration of `package`.
+    pub(crate) fn of(package: &cargo_metadata::Package) -> crate::Result<Self> {
+        parse(&package.metadata).map_err(|error| crate::Error::PackageConfig {
+            package: package.name.to_string(),
+            error,
+        })
+    }
+
+    /// Returns the `cargo publish` arguments for this package, given the options of all packages.
+    pub(crate) fn publish_args(&self, options: &Options) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.no_verify.or(options.no_verify).unwrap_or_default() {
+            args.push("--no-verify".to_string());
+        }
+        if !self.features.is_empty() {
+            args.extend(["--features".to_string(), self.features.join(",")]);
+        }
+        args.extend(options.extra_args.iter().cloned());
+        args.extend(self.extra_args.iter().cloned());
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PackageConfig, WorkspaceConfig};
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use std::time::Duration;
+
+    #[test]
+    fn explicit_flags_override_configured_flags() {
+        let config: WorkspaceConfig = super::parse(&serde_json::json!({
+                "publish-crates": {
+                    "no-verify": true,
+                    "resolve-versions": true,
+                    "include-dependencies": true,
+                    "include-dependants": true,
+                    "restore-manifests": true,
+                }
+        }))
+        .expect("configuration must parse");
+
+        let mut options = crate::tests::options("Cargo.toml".into());
+        options.no_verify = Some(false);
+        options.resolve_versions = Some(false);
+        options.restore_manifests = Some(false);
+        config.apply(&mut options);
+        sim_assert_eq!(
+            [
+                options.no_verify,
+                options.resolve_versions,
+                options.include_dependencies,
+                options.include_dependants,
+                options.restore_manifests,
+            ],
+            [
+                Some(false),
+                Some(false),
+                Some(true),
+                Some(true),
+                Some(false)
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_and_merges_workspace_and_package_configuration() {
+        let config: WorkspaceConfig = super::parse(&serde_json::json!({
+                "publish-crates": {
+                    "publish-delay": "1m 30s",
+                    "version-req-style": "caret",
+                    "exclude": ["internal-*"],
+                    "concurrency-limit": 2,
+                    "no-verify": true,
+                    "extra-args": ["--locked"],
+                }
+        }))
+        .expect("configuration must parse");
+
+        let mut options = crate::tests::options("Cargo.toml".into());
+        options.concurrency_limit = Some(8);
+        options.extra_args = vec!["--allow-dirty".to_string()];
+        config.apply(&mut options);
+        sim_assert_eq!(options.publish_delay, Some(Duration::from_secs(90)));
+        sim_assert_eq!(
+            options.version_req_style,
+            Some(crate::VersionReqStyle::Caret)
+        );
+        sim_assert_eq!(options.exclude, Some(vec!["internal-*".to_string()]));
+        sim_assert_eq!(options.concurrency_limit, Some(8));
+        sim_assert_eq!(options.no_verify, Some(true));
+        sim_assert_eq!(
+            options.extra_args,
+            vec!["--locked".to_string(), "--allow-dirty".to_string()]
+        );
+
+        let error = super::parse::<WorkspaceConfig>(
+            &serde_json::json!({"publish-crates": {"concurency-limit": 2}}),
+        )
+        .map_err(crate::Error::WorkspaceConfig)
+        .expect_err("unknown settings must be rejected");
+        assert!(
+            error.to_string().starts_with(
+                "invalid [workspace.metadata.publish-crates]: unknown field `concurency-limit`"
+            ),
+            "{error}"
+        );
+
+        let package: PackageConfig = super::parse(&serde_json::json!({"publish-crates": {
+                "registry": "private",
+                "extra-args": ["--jobs", "2"],
+                "features": ["codegen", "serde"],
+                "no-verify": false,
+                "max-retries": 1,
+        }}))
+        .expect("configuration must parse");
+        sim_assert_eq!(package.max_retries, Some(1));
+        options.extra_args = vec!["--locked".to_string()];
+        sim_assert_eq!(
+            package.publish_args(&options),
+            ["--features", "codegen,serde", "--locked", "--jobs", "2"].map(String::from)
+        );
+        options.extra_args = vec!["--index".to_string(), "https://example.com".to_string()];
+        sim_assert_eq!(
+            PackageConfig::default().publish_args(&options),
+            ["--no-verify", "--index", "https://example.com"].map(String::from)
+        );
+        sim_assert_eq!(
+            super::parse::<PackageConfig>(&serde_json::Value::Null)
+                .expect("missing configuration must parse"),
+            PackageConfig::default()
+        );
+    }
+}
@@ -0,0 +1,506 @@
+//! Errors of publishing, planning and bumping workspace packages.
+
+use crate::registry::RateLimited;
+use crate::{KnownFailure, PublishReport};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Result of the fallible functions of this crate.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Error returned by [`crate::publish`], [`crate::plan`], [`crate::graph`] and [`crate::bump`].
+///
+/// Errors that a user can fix have a [`Self::suggestion`], and errors after publishing started
+/// carry the [`PublishReport`] of the packages up to the failure. Match on [`Self::inner`] to
+/// handle the error that stopped publishing regardless of that wrapping.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// An option, or a value parsed for one, is invalid.
+    #[error("{0}")]
+    InvalidOptions(String),
+
+    /// `[workspace.metadata.publish-crates]` is invalid.
+    #[error("invalid [workspace.metadata.publish-crates]: {0}")]
+    WorkspaceConfig(#[source] serde_json::Error),
+
+    /// `[package.metadata.publish-crates]` of a package is invalid.
+    #[error("{package}: invalid [package.metadata.publish-crates]: {error}")]
+    PackageConfig {
+        /// Package name.
+        package: String,
+        /// Deserialization error.
+        #[source]
+        error: serde_json::Error,
+    },
+
+    /// A Cargo configuration file cannot be parsed.
+    #[error("failed to parse Cargo config {}: {error}", path.display())]
+    CargoConfig {
+        /// Path of the configuration file.
+        path: PathBuf,
+        /// Parse error.
+        #[source]
+        error: toml_edit::TomlError,
+    },
+
+    /// `cargo metadata` failed.
+    #[error(transparent)]
+    Metadata(#[from] cargo_metadata::Error),
+
+    /// A manifest cannot be parsed.
+    #[error("failed to parse {}: {error}", path.display())]
+    ManifestParse {
+        /// Path of the manifest.
+        path: PathBuf,
+        /// Parse error.
+        #[source]
+        error: toml_edit::TomlError,
+    },
+
+    /// A manifest lacks an expected entry, or cannot be snapshotted, written or restored.
+    #[error("{}: {message}", path.display())]
+    ManifestEdit {
+        /// Path of the manifest.
+        path: PathBuf,
+        /// Description of the problem.
+        message: String,
+    },
+
+    /// A local path dependency does not point to a workspace member.
+    #[error("{package}: could not resolve local dependency {}", path.display())]
+    UnresolvedLocalDependency {
+        /// Dependant package.
+        package: String,
+        /// Directory of the dependency.
+        path: PathBuf,
+    },
+
+    /// A selected package depends on a package that is neither selected nor published.
+    #[error(
+        "{package}: cannot publish because dependency {dependency} {version} is not selected and not published to {registry}"
+    )]
+    ExcludedDependency {
+        /// Dependant package.
+        package: String,
+        /// Dependency package.
+        dependency: String,
+        /// Workspace version of the dependency.
+        version: semver::Version,
+        /// Registry the dependency would be published to.
+        registry: String,
+    },
+
+    /// A selected package depends on a package that is never published.
+    #[error("{package}: cannot publish because dependency {dependency} will not be published")]
+    UnpublishableDependency {
+        /// Dependant package.
+        package: String,
+        /// Dependency package.
+        dependency: String,
+    },
+
+    /// A dependency has no specific version requirement.
+    #[error("{package}: dependency {dependency} has no specific version ({requirement})")]
+    MissingVersion {
+        /// Dependant package.
+        package: String,
+        /// Dependency package.
+        dependency: String,
+        /// Requirement of the dependency.
+        requirement: semver::VersionReq,
+    },
+
+    /// Local dependency requirements do not match the workspace versions of the dependencies.
+    #[error(
+        "local dependency requirements do not match the workspace versions:\n{}",
+        mismatches.join("\n")
+    )]
+    RequirementMismatch {
+        /// Sorted `path:line: ...` descriptions of the mismatches.
+        mismatches: Vec<String>,
+    },
+
+    /// [`crate::VersionReqStyle::KeepExistingIfCompatible`] keeps a requirement that does not
+    /// match the version.
+    #[error("requirement {requirement} does not match workspace version {version}")]
+    IncompatibleRequirement {
+        /// Existing requirement.
+        requirement: semver::VersionReq,
+        /// Workspace version of the dependency.
+        version: semver::Version,
+    },
+
+    /// A requirement on a local dependency cannot be written.
+    #[error("{package}: dependency {dependency}: {source}")]
+    Dependency {
+        /// Dependant package, or `workspace` for `[workspace.dependencies]`.
+        package: String,
+        /// Dependency package.
+        dependency: String,
+        /// Reason the requirement cannot be written.
+        #[source]
+        source: Box<Error>,
+    },
+
+    /// A version or version requirement is invalid.
+    #[error(transparent)]
+    Version(#[from] semver::Error),
+
+    /// A bump would decrease a version.
+    #[error("cannot bump {package} from {from} to the lower version {to}")]
+    VersionDecrease {
+        /// Package name, or `workspace` for `workspace.package.version`.
+        package: String,
+        /// Current version.
+        from: semver::Version,
+        /// Bumped version.
+        to: semver::Version,
+    },
+
+    /// Local dependencies form a cycle.
+    #[error(
+        "dependency cycle between local packages: {}\n{}",
+        packages.join(" → "),
+        edges.join("\n")
+    )]
+    Cycle {
+        /// Packages along the cycle, starting and ending with the same package.
+        packages: Vec<String>,
+        /// Edges of the cycle, such as `a -> b (normal, dev)`.
+        edges: Vec<String>,
+        /// Edges that only consist of a development dependency, such as `a on b`.
+        dev_edges: Vec<String>,
+    },
+
+    /// Packages cannot be ordered by their dependencies.
+    #[error("cannot order packages {}", packages.join(", "))]
+    Unschedulable {
+        /// Sorted names of the packages.
+        packages: Vec<String>,
+    },
+
+    /// The registry a package resolves to is missing from its `package.publish` list.
+    #[error("{package}: registry {registry} is not allowed by `package.publish = {allowed:?}`")]
+    RegistryNotAllowed {
+        /// Package name.
+        package: String,
+        /// Resolved registry.
+        registry: String,
+        /// Registries allowed by `package.publish`.
+        allowed: Vec<String>,
+    },
+
+    /// A single [`crate::Options::registry_token`] was given for packages published to several
+    /// registries.
+    #[error("a single registry token is ambiguous for packages published to {}", registries.join(", "))]
+    AmbiguousRegistryToken {
+        /// Sorted names of the registries.
+        registries: Vec<String>,
+    },
+
+    /// A registry query failed.
+    #[error("{0}")]
+    Registry(String),
+
+    /// A registry asked to retry a request later.
+    #[error(transparent)]
+    RateLimited(#[from] RateLimited),
+
+    /// An HTTP request to a registry failed.
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+
+    /// A crates.io API request failed.
+    #[error(transparent)]
+    CratesIo(#[from] crates_io_api::Error),
+
+    /// A git command failed.
+    #[error("git {} failed: {stderr}", args.join(" "))]
+    Git {
+        /// Arguments of the command.
+        args: Vec<String>,
+        /// Standard error of the command.
+        stderr: String,
+    },
+
+    /// Files changed since [`crate::Options::changed_since`] cannot be listed.
+    #[error("failed to list files changed since {git_ref}")]
+    ChangedSince {
+        /// Git revision.
+        git_ref: String,
+        /// Reason the files cannot be listed.
+        #[source]
+        source: Box<Error>,
+    },
+
+    /// The state file of [`crate::Options::resume`] cannot be parsed.
+    #[error("failed to parse state file {}: {error}", path.display())]
+    StateFile {
+        /// Path of the state file.
+        path: PathBuf,
+        /// Deserialization error.
+        #[source]
+        error: serde_json::Error,
+    },
+
+    /// `cargo publish` failed with an error that retrying cannot fix.
+    #[error("cargo publish of {package} {version} failed: {stderr}")]
+    PublishFatal {
+        /// Package name.
+        package: String,
+        /// Package version.
+        version: semver::Version,
+        /// HTTP status code of the registry response, if recognized.
+        code: Option<http::StatusCode>,
+        /// Known failure the output matched, if any.
+        failure: Option<KnownFailure>,
+        /// Standard error of the command.
+        stderr: String,
+    },
+
+    /// `cargo publish` still failed after the last retry.
+    #[error("cargo publish of {package} {version} failed after {attempts} attempts: {stderr}")]
+    RetriesExhausted {
+        /// Package name.
+        package: String,
+        /// Package version.
+        version: semver::Version,
+        /// Number of attempts.
+        attempts: usize,
+        /// Standard error of the last attempt.
+        stderr: String,
+    },
+
+    /// Waiting for the next `cargo publish` attempt would exceed [`crate::RetryPolicy::budget`].
+    #[error(
+        "cargo publish of {package} {version} failed after exhausting the retry budget of {}: {stderr}",
+        humantime::format_duration(*budget)
+    )]
+    RetryBudgetExhausted {
+        /// Package name.
+        package: String,
+        /// Package version.
+        version: semver::Version,
+        /// Exhausted budget.
+        budget: Duration,
+        /// Standard error of the last attempt.
+        stderr: String,
+    },
+
+    /// A published package did not become available on its registry in time.
+    #[error(
+        "exceeded timeout of {timeout:?} waiting for crate {package} {version} to be published"
+    )]
+    AvailabilityTimeout {
+        /// Package name.
+        package: String,
+        /// Package version.
+        version: semver::Version,
+        /// Timeout that was exceeded.
+        timeout: Duration,
+    },
+
+    /// A command other than `cargo publish` failed.
+    #[error("command {command} failed")]
+    Command {
+        /// Debug representation of the command.
+        command: String,
+    },
+
+    /// Publishing finished without publishing every selected package.
+    #[error("not all published: {}", packages.join(", "))]
+    NotAllPublished {
+        /// Sorted names of the unpublished packages.
+        packages: Vec<String>,
+    },
+
+    /// Publishing was stopped through [`crate::Options::interrupt`], for example on Ctrl-C.
+    #[error("interrupted")]
+    Interrupted,
+
+    /// Publishing failed after it started.
+    ///
+    /// The error that stopped publishing is the [`std::error::Error::source`] of this error.
+    #[error("publishing stopped")]
+    Failed {
+        /// Error that stopped publishing.
+        #[source]
+        error: Box<Error>,
+        /// Packages published, skipped and failed up to the error.
+        report: Box<PublishReport>,
+    },
+
+    /// Reading or writing a file, or running a command, failed.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    /// Serializing or deserializing JSON failed.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+impl Error {
+    /// Returns how the user can fix the error, if known.
+    #[must_use]
+    pub fn suggestion(&self) -> Option<String> {
+        let suggestion = match self {
+            Self::WorkspaceConfig(_) | Self::PackageConfig { .. } => {
+                "see the README for the supported settings"
+            }
+            Self::ExcludedDependency { .. } => {
+                "select the dependency, or use '--include-dependencies' to select it automatically"
+            }
+            Self::MissingVersion { .. } => {
+                "to automatically resolve versions of local workspace members, use '--resolve-versions'"
+            }
+            Self::RequirementMismatch { .. } => {
+                "update the requirements, or use '--resolve-versions' to rewrite them when publishing"
+            }
+            Self::Cycle { dev_edges, .. } if dev_edges.is_empty() => {
+                "packages that depend on each other cannot be published; move the shared code into a separate package"
+            }
+            Self::Cycle { dev_edges, .. } => {
+                return Some(format!(
+                    "remove the version requirement from the dev-dependency of {} so Cargo strips it when publishing",
+                    dev_edges.join(" and of ")
+                ));
+            }
+            Self::AmbiguousRegistryToken { .. } => {
+                "provide a token per registry in `CARGO_REGISTRY_TOKEN`, \
+                `CARGO_REGISTRIES_<NAME>_TOKEN` or Cargo credentials instead of '--registry-token'"
+            }
+            Self::RegistryNotAllowed { .. } => {
+                "select one of the allowed registries with '--registry' or `registry` in \
+                [package.metadata.publish-crates], or exclude the package"
+            }
+            Self::ChangedSince { git_ref, .. } => {
+                return Some(format!(
+                    "check that {git_ref:?} is a commit in this repository"
+                ));
+            }
+            Self::PublishFatal {
+                failure: Some(failure),
+                ..
+            } => return failure.suggestion().map(str::to_string),
+            Self::Dependency { source, .. } => return source.suggestion(),
+            Self::Failed { error, .. } => return error.suggestion(),
+            _ => return None,
+        };
+        Some(suggestion.to_string())
+    }
+
+    /// Returns the report of the packages handled before publishing failed.
+    #[must_use]
+    pub fn report(&self) -> Option<&PublishReport> {
+        match self {
+            Self::Failed { report, .. } => Some(report),
+            _ => None,
+        }
+    }
+
+    /// Returns the error that stopped publishing, unwrapping [`Self::Failed`].
+    #[must_use]
+    pub fn inner(&self) -> &Error {
+        match self {
+            Self::Failed { error, .. } => error.inner(),
+            _ => self,
+        }
+    }
+
+    /// Returns the error that stopped publishing, unwrapping [`Self::Failed`] and dropping its
+    /// report.
+    #[must_use]
+    pub fn into_inner(self) -> Error {
+        match self {
+            Self::Failed { error, .. } => error.into_inner(),
+            _ => self,
+        }
+    }
+
+    /// Returns an adapter that displays the error for users, followed by its
+    /// [`Self::suggestion`] and [`Self::report`].
+    #[must_use]
+    pub fn display_with_suggestion(&self) -> DisplayWithSuggestion<'_> {
+        DisplayWithSuggestion(self)
+    }
+}
+
+/// Displays an [`Error`] with its suggestion and report, see [`Error::display_with_suggestion`].
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayWithSuggestion<'a>(&'a Error);
+
+impl std::fmt::Display for DisplayWithSuggestion<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0.inner())?;
+        if let Some(suggestion) = self.0.suggestion() {
+            write!(f, "\n\nSuggestion: {suggestion}")?;
+        }
+        if let Some(report) = self.0.report() {
+            write!(f, "\n\nReport:\n{}", report.to_string().trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    #[test]
+    fn unwraps_errors_after_publishing_started() {
+        let error = Error::Failed {
+            error: Box::new(Error::Interrupted),
+            report: Box::default(),
+        };
+        assert!(matches!(error.inner(), Error::Interrupted), "{error:?}");
+        assert!(error.report().is_some());
+        sim_assert_eq!(error.to_string(), "publishing stopped");
+        sim_assert_eq!(
+            std::error::Error::source(&error).map(ToString::to_string),
+            Some("interrupted".to_string())
+        );
+
+        sim_assert_eq!(
+            error.display_with_suggestion().to_string(),
+            "interrupted\n\nReport:\npackage  version  outcome  attempts  upload  availability  delay  error"
+        );
+
+        assert!(matches!(error.into_inner(), Error::Interrupted));
+
+        let error = Error::Interrupted;
+        assert!(matches!(error.inner(), Error::Interrupted), "{error:?}");
+        assert!(error.report().is_none());
+    }
+    #[test]
+    fn displays_the_cause_of_dependency_errors() {
+        let error = Error::Dependency {
+            package: "app".to_string(),
+            dependency: "core".to_string(),
+            source: Box::new(Error::IncompatibleRequirement {
+                requirement: "^1".parse().expect("requirement must parse"),
+                version: semver::Version::new(2, 0, 0),
+            }),
+        };
+        sim_assert_eq!(
+            error.to_string(),
+            "app: dependency core: requirement ^1 does not match workspace version 2.0.0"
+        );
+        assert!(std::error::Error::source(&error).is_some());
+    }
+
+    #[test]
+    fn displays_suggestions() {
+        let error = Error::MissingVersion {
+            package: "app".to_string(),
+            dependency: "core".to_string(),
+            requirement: semver::VersionReq::STAR,
+        };
+        sim_assert_eq!(
+            error.display_with_suggestion().to_string(),
+            "app: dependency core has no specific version (*)\n\n\
+            Suggestion: to automatically resolve versions of local workspace members, use '--resolve-versions'"
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! Machine-readable events emitted while publishing.
+
+use crate::log;
+use crate::{Error, Plan, PublishError, SkipReason};
+use parking_lot::Mutex;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Output format of publishing progress.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageFormat {
+    /// Human-readable log lines only.
+    #[default]
+    Human,
+    /// Prints every [`Event`] as one JSON object per line to stdout, and log lines to stderr.
+    Json,
+}
+
+impl std::fmt::Display for MessageFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let format = match self {
+            Self::Human => "human",
+            Self::Json => "json",
+        };
+        write!(f, "{format}")
+    }
+}
+
+impl std::str::FromStr for MessageFormat {
+    type Err = Error;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.to_ascii_lowercase().as_str() {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            _ => Err(Error::InvalidOptions(format!(
+                "unknown message format {format:?} (expected human or json)"
+            ))),
+        }
+    }
+}
+
+/// A publishing progress event.
+///
+/// Serialized as a JSON object whose `reason` field names the variant in snake case, like the
+/// messages of `cargo --message-format json`.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum Event {
+    /// The publish order, emitted once before any package is published.
+    Plan(Plan),
+    /// A package is not published.
+    Skipped {
+        /// Package name.
+        name: String,
+        /// Package version.
+        version: semver::Version,
+        /// Why the package is skipped.
+        skip_reason: SkipReason,
+    },
+    /// `cargo publish` starts for a package.
+    PublishStarted {
+        /// Package name.
+        name: String,
+        /// Package version.
+        version: semver::Version,
+    },
+    /// A `cargo publish` attempt failed.
+    AttemptFailed {
+        /// Package name.
+        name: String,
+        /// Package version.
+        version: semver::Version,
+        /// One-based attempt number.
+        attempt: usize,
+        /// Classification of the failure.
+        error: PublishError,
+        /// Standard error of `cargo publish`.
+        message: String,
+    },
+    /// A failed attempt is retried after a delay.
+    RetryScheduled {
+        /// Package name.
+        name: String,
+        /// Package version.
+        version: semver::Version,
+        /// One-based number of the next attempt.
+        attempt: usize,
+        /// Delay before the next attempt in seconds.
+        delay_secs: u64,
+        /// Time of the next attempt in RFC 3339 format.
+        at: String,
+    },
+    /// `cargo publish` uploaded the package.
+    UploadDone {
+        /// Package name.
+        name: String,
+        /// Package version.
+        version: semver::Version,
+    },
+    /// The registry was asked whether the uploaded version is available.
+    AvailabilityProbe {
+        /// Package name.
+        name: String,
+        /// Package version.
+        version: semver::Version,
+        /// Registry that was queried.
+        registry: String,
+        /// Whether the registry serves the version.
+        available: bool,
+    },
+    /// The package is published and its dependants may proceed.
+    Published {
+        /// Package name.
+        name: String,
+        /// Package version.
+        version: semver::Version,
+    },
+    /// Publishing the package failed and the run stops.
+    Failed {
+        /// Package name.
+        name: String,
+        /// Package version.
+        version: semver::Version,
+        /// The error that failed the package.
+        error: String,
+    },
+    /// The run completed.
+    RunFinished {
+        /// Whether every selected package was published.
+        success: bool,
+        /// The error that ended the run.
+        error: Option<String>,
+    },
+}
+
+/// Receives publishing progress from [`crate::publish`].
+///
+/// Every method has an empty default implementation, so observers only implement the
+/// notifications they need. Methods are called from the publishing tasks, possibly concurrently
+/// for different packages, and should return quickly.
+pub trait PublishObserver: std::fmt::Debug + Send + Sync {
+    /// Called for every event, before the specific notification below.
+    fn on_event(&self, _event: &Event) {}
+
+    /// Called once with the publish order before any package is published.
+    fn on_plan(&self, _plan: &Plan) {}
+
+    /// Called for every package that is not published.
+    fn on_skipped(&self, _name: &str, _version: &semver::Version, _reason: SkipReason) {}
+
+    /// Called when publishing a package starts.
+    fn on_start(&self, _name: &str, _version: &semver::Version) {}
+
+    /// Called when a `cargo publish` attempt fails, with its classification and standard error.
+    fn on_attempt(
+        &self,
+        _name: &str,
+        _version: &semver::Version,
+        _attempt: usize,
+        _error: PublishError,
+        _message: &str,
+    ) {
+    }
+
+    /// Called when a failed attempt is retried as attempt `attempt` after `delay`.
+    fn on_retry(&self, _name: &str, _version: &semver::Version, _attempt: usize, _delay: Duration) {
+    }
+
+    /// Called when a package is published and its dependants may proceed.
+    fn on_published(&self, _name: &str, _version: &semver::Version) {}
+
+    /// Called when publishing a package fails.
+    fn on_failed(&self, _name: &str, _version: &semver::Version, _error: &str) {}
+}
+
+/// Writes events in the selected [`MessageFormat`] and forwards them to a [`PublishObserver`].
+pub(crate) struct Events {
+    format: MessageFormat,
+    writer: Mutex<Box<dyn Write + Send>>,
+    observer: Option<Arc<dyn PublishObserver>>,
+}
+
+impl std::fmt::Debug for Events {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Events")
+            .field("format", &self.format)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Events {
+    /// Creates an event writer that prints to stdout.
+    pub(crate) fn new(format: MessageFormat, observer: Option<Arc<dyn PublishObserver>>) -> Self {
+        Self::to_writer(format, Box::new(std::io::stdout()), observer)
+    }
+
+    /// Creates an event writer that prints to `writer`.
+    pub(crate) fn to_writer(
+        format: MessageFormat,
+        writer: Box<dyn Write + Send>,
+        observer: Option<Arc<dyn PublishObserver>>,
+    ) -> Self {
+        Self {
+            format,
+            writer: Mutex::new(writer),
+            observer,
+        }
+    }
+
+    /// Notifies the observer and writes the event in the selected format.
+    pub(crate) fn emit(&self, event: &Event) {
+        if let Some(observer) = &self.observer {
+            notify(observer.as_ref(), event);
+        }
+        if self.format == MessageFormat::Json {
+            self.write(event);
+        }
+    }
+
+    /// Writes an event as a single JSON line.
+    fn write(&self, event: &Event) {
+        let line = match serde_json::to_string(event) {
+            Ok(line) => line,
+            Err(err) => {
+                log::warning!(
+                    log::Log::new(self.format),
+                    "failed to serialize event: {err}"
+                );
+                return;
+            }
+        };
+        let mut writer = self.writer.lock();
+        // A closed consumer must not abort publishing.
+        let _ = writeln!(writer, "{line}").and_then(|()| writer.flush());
+    }
+}
+
+/// Calls the observer method matching `event`.
+fn notify(observer: &dyn PublishObserver, event: &Event) {
+    observer.on_event(event);
+    match event {
+        Event::Plan(plan) => observer.on_plan(plan),
+        Event::Skipped {
+            name,
+            version,
+            skip_reason,
+        } => observer.on_skipped(name, version, *skip_reason),
+        Event::PublishStarted { name, version } => observer.on_start(name, version),
+        Event::AttemptFailed {
+            name,
+            version,
+            attempt,
+            error,
+            message,
+        } => observer.on_attempt(name, version, *attempt, *error, message),
+        Event::RetryScheduled {
+            name,
+            version,
+            attempt,
+            delay_secs,
+            ..
+        } => observer.on_retry(name, version, *attempt, Duration::from_secs(*delay_secs)),
+        Event::Published { name, version } => observer.on_published(name, version),
+        Event::Failed {
+            name,
+            version,
+            error,
+        } => observer.on_failed(name, version, error),
+        Event::UploadDone { .. } | Event::AvailabilityProbe { .. } | Event::RunFinished { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, MessageFormat};
+    use crate::PublishError;
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    #[test]
+    fn serializes_events_with_reason_tags() {
+        let event = Event::AttemptFailed {
+            name: "foo".to_string(),
+            version: semver::Version::new(1, 2, 3),
+            attempt: 2,
+            error: PublishError::Retryable(http::StatusCode::TOO_MANY_REQUESTS),
+            message: "429 Too Many Requests".to_string(),
+        };
+        sim_assert_eq!(
+            serde_json::to_value(&event).expect("event must serialize"),
+            serde_json::json!({
+                "reason": "attempt_failed",
+                "name": "foo",
+                "version": "1.2.3",
+                "attempt": 2,
+                "error": {"kind": "retryable", "status": 429},
+                "message": "429 Too Many Requests",
+            })
+        );
+        sim_assert_eq!(
+            "JSON".parse::<MessageFormat>().ok(),
+            Some(MessageFormat::Json)
+        );
+    }
+}
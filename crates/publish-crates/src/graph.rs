@@ -0,0 +1,226 @@
+//! Export of the publish dependency graph.
+
+use crate::{Error, Options, Package};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Output format of [`graph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GraphFormat {
+    /// Graphviz DOT.
+    Dot,
+    /// Mermaid flowchart, suitable for Markdown.
+    Mermaid,
+    /// JSON object with `nodes` and `edges` arrays.
+    Json,
+}
+
+impl std::fmt::Display for GraphFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let format = match self {
+            Self::Dot => "dot",
+            Self::Mermaid => "mermaid",
+            Self::Json => "json",
+        };
+        write!(f, "{format}")
+    }
+}
+
+impl std::str::FromStr for GraphFormat {
+    type Err = Error;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.to_ascii_lowercase().as_str() {
+            "dot" => Ok(Self::Dot),
+            "mermaid" => Ok(Self::Mermaid),
+            "json" => Ok(Self::Json),
+            _ => Err(Error::InvalidOptions(format!(
+                "unknown graph format {format:?} (expected dot, mermaid or json)"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Node {
+    name: String,
+    version: String,
+    publishable: bool,
+    should_publish: bool,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Edge {
+    /// The dependant package.
+    from: String,
+    /// The dependency package.
+    to: String,
+    kinds: Vec<cargo_metadata::DependencyKind>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+impl Graph {
+    fn new(packages: &HashMap<PathBuf, Arc<Package>>) -> Self {
+        let mut packages = packages.values().collect::<Vec<_>>();
+        packages.sort_by(|a, b| a.inner.name.cmp(&b.inner.name));
+
+        let nodes = packages
+            .iter()
+            .map(|package| Node {
+                name: package.inner.name.to_string(),
+                version: package.inner.version.to_string(),
+                publishable: package.publishable,
+                should_publish: package.should_publish(),
+            })
+            .collect();
+        let edges = packages
+            .iter()
+            .flat_map(|package| {
+                let mut edges = package
+                    .deps
+                    .read()
+                    .values()
+                    .map(|dep| Edge {
+                        from: package.inner.name.to_string(),
+                        to: dep.package.inner.name.to_string(),
+                        kinds: dep.kinds.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                edges.sort_by(|a, b| a.to.cmp(&b.to));
+                edges
+            })
+            .collect();
+        Self { nodes, edges }
+    }
+
+    fn edge_label(edge: &Edge) -> String {
+        edge.kinds
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn to_dot(&self) -> String {
+        let mut out = String::from("digraph publish {\n");
+        for node in &self.nodes {
+            let style = if node.should_publish {
+                ""
+            } else {
+                ", style=dashed"
+            };
+            let _ = writeln!(
+                out,
+                "  {:?} [label=\"{}\\n{}\", version={:?}, publishable={}, should_publish={}{style}];",
+                node.name,
+                node.name,
+                node.version,
+                node.version,
+                node.publishable,
+                node.should_publish,
+            );
+        }
+        for edge in &self.edges {
+            let _ = writeln!(
+                out,
+                "  {:?} -> {:?} [label={:?}];",
+                edge.from,
+                edge.to,
+                Self::edge_label(edge)
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    fn to_mermaid(&self) -> String {
+        // Package names may contain characters Mermaid does not accept in node IDs.
+        let ids = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.name.as_str(), format!("p{index}")))
+            .collect::<HashMap<_, _>>();
+        let mut out = String::from("graph TD\n");
+        for node in &self.nodes {
+            let Some(id) = ids.get(node.name.as_str()) else {
+                continue;
+            };
+            let class = if node.should_publish {
+                ""
+            } else {
+                ":::skipped"
+            };
+            let _ = writeln!(out, "  {id}[\"{}@{}\"]{class}", node.name, node.version);
+        }
+        for edge in &self.edges {
+            let (Some(from), Some(to)) = (ids.get(edge.from.as_str()), ids.get(edge.to.as_str()))
+            else {
+                continue;
+            };
+            let _ = writeln!(out, "  {from} -->|{}| {to}", Self::edge_label(edge));
+        }
+        out.push_str("  classDef skipped stroke-dasharray: 5 5\n");
+        out
+    }
+}
+
+/// Renders the dependency graph of a package map.
+fn render(packages: &HashMap<PathBuf, Arc<Package>>, format: GraphFormat) -> crate::Result<String> {
+    let graph = Graph::new(packages);
+    Ok(match format {
+        GraphFormat::Dot => graph.to_dot(),
+        GraphFormat::Mermaid => graph.to_mermaid(),
+        GraphFormat::Json => format!("{}\n", serde_json::to_string_pretty(&graph)?),
+    })
+}
+
+/// Renders the publish dependency graph of the workspace without side effects.
+///
+/// Nodes are workspace packages with their version and whether they are publishable and selected
+/// for publishing. Edges point from a dependant to its local dependency and carry the dependency
+/// kinds (`normal`, `dev`, `build`). Only dependencies of selected packages form edges, matching
+/// the order [`crate::publish`] uses.
+///
+/// # Errors
+///
+/// Returns an error when the options, configuration or Cargo metadata are invalid, dependency
+/// versions cannot be resolved, a selected package depends on an excluded package, or local
+/// dependencies form a cycle.
+pub async fn graph(options: Options, format: GraphFormat) -> crate::Result<String> {
+    let mut options = options.with_workspace_config()?;
+    // Rendering the graph never modifies manifests.
+    options.dry_run = true;
+    let (_, packages) = crate::load_packages(&options, None).await?;
+    render(&packages, format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GraphFormat;
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    #[test]
+    fn parses_graph_formats() {
+        sim_assert_eq!("dot".parse::<GraphFormat>().ok(), Some(GraphFormat::Dot));
+        sim_assert_eq!(
+            "Mermaid".parse::<GraphFormat>().ok(),
+            Some(GraphFormat::Mermaid)
+        );
+        sim_assert_eq!("json".parse::<GraphFormat>().ok(), Some(GraphFormat::Json));
+        sim_assert_eq!(
+            "svg"
+                .parse::<GraphFormat>()
+                .expect_err("unknown formats must be rejected")
+                .to_string(),
+            r#"unknown graph format "svg" (expected dot, mermaid or json)"#
+        );
+    }
+}
@@ -7,8 +7,8 @@
 //!
 //! ```no_run
 //! # // Uses `no_run` because publishing requires a Cargo workspace and registry access.
-//! # async fn example() -> color_eyre::eyre::Result<()> {
-//! use publish_crates::{Options, publish};
+//! # async fn example() -> publish_crates::Result<()> {
+//! use publish_crates::{MessageFormat, Options, RetryPolicy, VersionReqStyle, publish};
 //! use std::path::PathBuf;
 //!
 //! publish(Options {
@@ -16,37 +16,94 @@
 //!     registry_token: None,
 //!     dry_run: true,
 //!     publish_delay: None,
-//!     no_verify: false,
-//!     resolve_versions: false,
+//!     no_verify: None,
+//!     resolve_versions: None,
+//!     version_req_style: Some(VersionReqStyle::Exact),
 //!     include: None,
 //!     exclude: None,
+//!     include_dependencies: None,
+//!     changed_since: None,
+//!     include_dependants: None,
 //!     max_retries: None,
+//!     retry_policy: RetryPolicy::default(),
 //!     concurrency_limit: Some(4),
 //!     extra_args: Vec::new(),
+//!     registry_client: None,
+//!     state_file: None,
+//!     resume: false,
+//!     message_format: MessageFormat::Human,
+//!     observer: None,
+//!     restore_manifests: None,
+//!     interrupt: None,
 //! })
 //! .await?;
 //! # Ok(())
 //! # }
 //! ```
 
-use action_core as action;
 use cargo_metadata::DependencyKind;
 use cargo_metadata::cargo_platform::Platform;
-use color_eyre::{Section, eyre};
 use futures::Future;
 use futures::stream::{self, FuturesUnordered, StreamExt};
 use parking_lot::{Mutex, RwLock};
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
 use tokio::sync::Semaphore;
 use tokio::time::{Duration, Instant, interval, sleep};
 
+mod bump;
+mod changes;
+mod classify;
+mod config;
+mod error;
+mod events;
+mod graph;
+mod log;
+mod manifest;
+mod plan;
+mod registry;
+mod report;
+mod requirements;
+mod retry;
+mod selection;
+mod state;
+
+pub use bump::{BumpLevel, BumpReport, BumpedPackage, bump};
+use classify::classify_publish_error;
+pub use classify::{FailureCategory, KnownFailure, PublishError};
+use config::{PackageConfig, WorkspaceConfig};
+pub use error::{DisplayWithSuggestion, Error, Result};
+use events::Events;
+pub use events::{Event, MessageFormat, PublishObserver};
+pub use graph::{GraphFormat, graph};
+use manifest::{ManifestSnapshots, write_manifest};
+pub use plan::{Plan, PlannedPackage, SkipReason, SkippedPackage, plan};
+use registry::{CargoConfig, Registry, argument_value};
+pub use registry::{
+    CratesIoClient, GitIndexClient, InMemoryRegistry, PublishedVersion, RateLimited,
+    RegistryClient, SparseIndexClient,
+};
+use report::PackageStats;
+pub use report::{Outcome, PackageReport, PublishReport};
+pub use requirements::VersionReqStyle;
+pub use retry::{RetryClass, RetryPolicy};
+use selection::{Selection, is_publishable};
+pub use state::PackageState;
+use state::StateStore;
+
 const DATETIME_FORMAT: &[time::format_description::BorrowedFormatItem<'static>] =
     time::macros::format_description!("[hour]:[minute]:[second]");
 
 /// Configures workspace package selection and publishing behavior.
+///
+/// Unset options, including flags left at [`None`], are read from
+/// `[workspace.metadata.publish-crates]` of the workspace manifest. [`Self::retry_policy`] is not
+/// configurable there.
+/// Each package can override [`Self::no_verify`], [`Self::publish_delay`], [`Self::max_retries`]
+/// and the registry, enable features, and append to [`Self::extra_args`] in
+/// `[package.metadata.publish-crates]`.
 #[derive(Debug)]
 pub struct Options {
     /// Path to a package or workspace directory, or directly to its `Cargo.toml`.
@@ -54,7 +111,12 @@ pub struct Options {
 
     /// Registry token passed to Cargo as `CARGO_REGISTRY_TOKEN`.
     ///
-    /// A value of [`None`] leaves Cargo's existing credentials and environment unchanged.
+    /// Packages published to a named alternative registry receive the token as
+    /// `CARGO_REGISTRIES_<NAME>_TOKEN` instead, and it authenticates index requests to registries
+    /// that require it. The token only applies when all selected packages publish to the same
+    /// registry; runs that publish to several registries fail with
+    /// [`Error::AmbiguousRegistryToken`]. Other registries, and every registry with [`None`], use
+    /// Cargo's existing credentials and environment.
     pub registry_token: Option<String>,
 
     /// Runs Cargo's publishing checks without uploading packages.
@@ -70,35 +132,72 @@ pub struct Options {
     pub publish_delay: Option<Duration>,
 
     /// Passes `--no-verify` to `cargo publish`.
-    pub no_verify: bool,
+    ///
+    /// [`None`] uses the workspace configuration, or `false`.
+    pub no_verify: Option<bool>,
 
     /// Replaces local path dependency requirements with exact workspace package versions.
     ///
-    /// A local dependency such as `{ path = "../some/path" }` receives the version of the package
-    /// at that path. Existing version requirements are also replaced.
+    /// A local dependency such as `{ path = "../some/path" }` receives a requirement on the version
+    /// of the package at that path, written in [`Self::version_req_style`]. Existing version
+    /// requirements are also replaced unless that style keeps them.
     ///
     /// Versionless development dependencies on packages with `publish = false` remain path-only,
     /// so Cargo omits them from the published manifest. Other private local dependencies prevent
     /// publication.
     ///
-    /// Unless [`Self::dry_run`] is enabled, this updates affected Cargo manifests.
-    pub resolve_versions: bool,
+    /// Unless [`Self::dry_run`] is enabled, this updates affected Cargo manifests. [`None`] uses
+    /// the workspace configuration, or `false`.
+    pub resolve_versions: Option<bool>,
+
+    /// Requirement written for local dependencies by [`Self::resolve_versions`].
+    ///
+    /// Applies to member manifests and `[workspace.dependencies]` alike. [`None`] uses
+    /// [`VersionReqStyle::Exact`].
+    pub version_req_style: Option<VersionReqStyle>,
 
-    /// Workspace package names eligible for publishing.
+    /// Workspace packages eligible for publishing.
     ///
-    /// [`None`] or an empty list includes every publishable workspace package.
+    /// Each entry is a package name or a glob pattern such as `mylib-*`. Entries containing a `/`
+    /// match package directories relative to the workspace root instead, such as
+    /// `crates/internal/**`. [`None`] or an empty list includes every publishable workspace
+    /// package.
     pub include: Option<Vec<String>>,
 
-    /// Workspace package names excluded from publishing.
+    /// Workspace packages excluded from publishing, in the format of [`Self::include`].
     ///
     /// Exclusion takes precedence over [`Self::include`].
     pub exclude: Option<Vec<String>>,
 
+    /// Also selects the local dependencies of selected packages, transitively.
+    ///
+    /// Without this option, a selected package that depends on a deselected local package cannot
+    /// be published. Excluded dependencies stay excluded. [`None`] uses the workspace
+    /// configuration, or `false`.
+    pub include_dependencies: Option<bool>,
+
+    /// Git revision that limits publishing to packages with files changed since then.
+    ///
+    /// The working tree, including untracked files that are not ignored, is compared against the
+    /// revision, and every changed file is attributed to the package whose directory contains it.
+    /// Packages are further limited by [`Self::include`] and [`Self::exclude`].
+    pub changed_since: Option<String>,
+
+    /// Also selects every package that transitively depends on a package changed since
+    /// [`Self::changed_since`].
+    ///
+    /// [`None`] uses the workspace configuration, or `false`.
+    pub include_dependants: Option<bool>,
+
     /// Maximum retries after the initial attempt for an intermittent publishing error.
     ///
-    /// [`None`] uses twice the number of discovered workspace packages.
+    /// [`None`] uses twice the number of discovered workspace packages. The delays between
+    /// retries come from [`Self::retry_policy`], see [`RetryPolicy::default`] for its defaults.
     pub max_retries: Option<usize>,
 
+    /// Backoff between attempts after intermittent publishing errors.
+    pub retry_policy: RetryPolicy,
+
     /// Maximum number of packages to publish concurrently.
     ///
     /// [`None`] uses four. A value of zero is invalid.
@@ -106,27 +205,117 @@ pub struct Options {
 
     /// Additional arguments passed to every `cargo publish` invocation.
     ///
-    /// Each element is passed as one argument, without shell interpretation.
+    /// Each element is passed as one argument, without shell interpretation. A `--registry` or
+    /// `--index` argument also selects the registry queried for package availability.
     pub extra_args: Vec<String>,
+
+    /// Client used to query the registry for published versions.
+    ///
+    /// [`None`] selects a client for each package's target registry: [`CratesIoClient`] for
+    /// crates.io, [`SparseIndexClient`] for `sparse+` indexes and [`GitIndexClient`] for git
+    /// indexes.
+    pub registry_client: Option<Arc<dyn RegistryClient>>,
+
+    /// JSON file recording the [`PackageState`] of every selected package.
+    ///
+    /// The file is rewritten after each state transition so an interrupted run can be resumed.
+    /// [`None`] disables the state file unless [`Self::resume`] is enabled, which then uses
+    /// `<target-dir>/publish-crates/state.json`. The state file is not used during a dry-run.
+    pub state_file: Option<PathBuf>,
+
+    /// Continues an interrupted run recorded in [`Self::state_file`].
+    ///
+    /// Packages the previous run uploaded are verified against the registry and, once available,
+    /// treated as published. All other packages are published as usual.
+    pub resume: bool,
+
+    /// Output format of publishing progress.
+    ///
+    /// [`MessageFormat::Json`] prints every [`Event`] of [`publish`] as one JSON object per line to
+    /// stdout and writes the human-readable log lines to stderr instead.
+    pub message_format: MessageFormat,
+
+    /// Receives every [`Event`] of [`publish`], independent of [`Self::message_format`].
+    pub observer: Option<Arc<dyn PublishObserver>>,
+
+    /// Restores workspace manifests and `Cargo.lock` after [`publish`] returns.
+    ///
+    /// The files are snapshotted before [`Self::resolve_versions`] modifies them and written back
+    /// when the run succeeds, fails, or is stopped through [`Self::interrupt`], leaving the
+    /// working tree as it was after Cargo metadata was first loaded. [`None`] uses the workspace
+    /// configuration, or `false`.
+    pub restore_manifests: Option<bool>,
+
+    /// Stops [`publish`] when notified, for example by a Ctrl-C handler of the caller.
+    ///
+    /// Running `cargo` processes are killed, manifests are restored with
+    /// [`Self::restore_manifests`], and [`publish`] returns [`Error::Interrupted`]. The library
+    /// never handles signals itself. [`tokio::sync::Notify::notify_one`] also interrupts a run
+    /// that is not waiting for the notification yet.
+    pub interrupt: Option<Arc<tokio::sync::Notify>>,
 }
 
 impl Options {
-    fn validate(&self) -> eyre::Result<()> {
+    fn validate(&self) -> Result<()> {
         if self.concurrency_limit == Some(0) {
-            eyre::bail!("concurrency limit must be greater than zero");
+            return Err(Error::InvalidOptions(
+                "concurrency limit must be greater than zero".to_string(),
+            ));
+        }
+        self.retry_policy.validate()
+    }
+
+    /// Returns where log lines of [`Self::message_format`] go.
+    fn log(&self) -> log::Log {
+        log::Log::new(self.message_format)
+    }
+
+    /// Fills unset options from `[workspace.metadata.publish-crates]` of the workspace manifest
+    /// and validates the result.
+    ///
+    /// Every public entry point calls this once, so internal functions take validated options.
+    fn with_workspace_config(mut self) -> Result<Self> {
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(self.manifest_path())
+            .no_deps()
+            .exec()?;
+        WorkspaceConfig::from_metadata(&metadata)?.apply(&mut self);
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Returns the workspace manifest that [`Self::path`] points to.
+    fn manifest_path(&self) -> PathBuf {
+        if self.path.is_file() {
+            self.path.clone()
+        } else {
+            self.path.join("Cargo.toml")
         }
-        Ok(())
     }
 }
 
+/// A local dependency edge in the publish graph.
+struct LocalDependency {
+    package: Arc<Package>,
+    /// Dependency kinds that declare the edge, in manifest order.
+    kinds: Vec<DependencyKind>,
+}
+
 /// Tracks publishing state and local dependency edges for a Cargo package.
 struct Package {
     inner: cargo_metadata::Package,
     path: PathBuf,
     publishable: bool,
-    should_publish: bool,
+    skip_reason: Option<SkipReason>,
+    config: PackageConfig,
+    registry: Registry,
+    /// [`Options::registry_token`] if it authenticates [`Self::registry`].
+    registry_token: Option<String>,
+    registry_client: Arc<dyn RegistryClient>,
+    log: log::Log,
     published: Mutex<bool>,
-    deps: RwLock<HashMap<String, Arc<Package>>>,
+    stats: Mutex<PackageStats>,
+    deps: RwLock<HashMap<String, LocalDependency>>,
     dependants: RwLock<HashMap<String, Arc<Package>>>,
 }
 
@@ -151,94 +340,220 @@ impl std::fmt::Display for Package {
 }
 
 impl Package {
+    /// Records an edge of `kind` from this package to the local package `dependency`.
+    fn add_local_dependency(self: &Arc<Self>, dependency: &Arc<Package>, kind: DependencyKind) {
+        let mut deps = self.deps.write();
+        let edge = deps
+            .entry(dependency.inner.name.to_string())
+            .or_insert_with(|| LocalDependency {
+                package: dependency.clone(),
+                kinds: Vec::new(),
+            });
+        if !edge.kinds.contains(&kind) {
+            edge.kinds.push(kind);
+        }
+        drop(deps);
+
+        dependency
+            .dependants
+            .write()
+            .insert(self.inner.name.to_string(), self.clone());
+    }
+
     /// Returns `true` if the package has been successfully published.
     pub fn published(&self) -> bool {
         *self.published.lock()
     }
 
+    /// Returns `true` if the package is selected for publishing.
+    pub fn should_publish(&self) -> bool {
+        self.skip_reason.is_none()
+    }
+
     /// Checks whether the package is ready for publishing.
     ///
     /// A package can be published if all its dependencies have been published.
     pub fn ready(&self) -> bool {
-        self.deps.read().values().all(|d| d.published())
+        self.deps.read().values().all(|d| d.package.published())
     }
 
-    /// Checks whether this package version is downloadable from crates.io.
-    pub async fn is_available(&self) -> eyre::Result<bool> {
-        use crates_io_api::{AsyncClient, Error as RegistryError};
-        use semver::Version;
-
-        let api = AsyncClient::new(
-            "publish_crates (https://github.com/romnn/publish-crates)",
-            std::time::Duration::from_secs(1),
-        )?;
-
-        let info = match api.get_crate(&self.inner.name).await {
-            Ok(info) => info,
-            Err(RegistryError::NotFound(_)) => return Ok(false),
-            Err(err) => return Err(err.into()),
-        };
+    /// Records a state transition, warning instead of failing when the state file cannot be written.
+    fn set_state(&self, store: &StateStore, state: PackageState) {
+        if let Err(err) = store.set(&self.inner.name, &self.inner.version, state) {
+            log::warning!(
+                self.log,
+                "[{}@{}] failed to record state {}: {}",
+                self.inner.name,
+                self.inner.version,
+                state,
+                err
+            );
+        }
+    }
 
-        let mut versions = info
-            .versions
-            .iter()
-            .filter_map(|v| match Version::parse(&v.num) {
-                Ok(version) => Some((version, v)),
-                Err(_) => None,
-            });
-        let Some((_, version)) = versions.find(|(ver, _)| ver == &self.inner.version) else {
-            return Ok(false);
-        };
+    /// Marks the package as published so its dependants may proceed.
+    fn mark_published(&self, store: &StateStore, events: &Events) {
+        *self.published.lock() = true;
+        self.stats.lock().outcome = Some(Outcome::Published);
+        self.set_state(store, PackageState::Done);
+        events.emit(&Event::Published {
+            name: self.inner.name.to_string(),
+            version: self.inner.version.clone(),
+        });
+    }
 
-        let client = reqwest::Client::new();
-        let dl_response = client
-            .head(format!("https://crates.io{}", version.dl_path))
-            .send()
-            .await?;
-        Ok(dl_response.status() == reqwest::StatusCode::OK)
+    /// Checks whether this package version is downloadable from its target registry.
+    pub async fn is_available(&self) -> Result<bool> {
+        self.registry_client
+            .is_downloadable(&self.inner.name, &self.inner.version)
+            .await
     }
 
     /// Waits until the published package is available on the registry.
     pub async fn wait_package_available(
         &self,
         timeout: impl Into<Option<Duration>>,
-    ) -> eyre::Result<()> {
+        events: &Events,
+    ) -> Result<()> {
         let timeout = timeout.into().unwrap_or_else(|| Duration::from_mins(2));
         let start = Instant::now();
         let mut ticker = interval(Duration::from_secs(5));
         loop {
             ticker.tick().await;
-            action::info!(
-                "[{}@{}] checking if available",
+            log::info!(
+                self.log,
+                "[{}@{}] checking if available on {}",
                 self.inner.name,
                 self.inner.version,
+                self.registry,
             );
-            if self.is_available().await? {
+            let available = match self.is_available().await {
+                Ok(available) => available,
+                Err(Error::RateLimited(limit)) => {
+                    let wait = limit
+                        .retry_after
+                        .duration_since(std::time::SystemTime::now())
+                        .unwrap_or_default();
+                    if start.elapsed() + wait > timeout {
+                        return Err(self.availability_timeout(timeout));
+                    }
+                    log::warning!(
+                        self.log,
+                        "[{}@{}] {} asks to retry after {}",
+                        self.inner.name,
+                        self.inner.version,
+                        self.registry,
+                        humantime::format_rfc3339_seconds(limit.retry_after),
+                    );
+                    sleep(wait).await;
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+            events.emit(&Event::AvailabilityProbe {
+                name: self.inner.name.to_string(),
+                version: self.inner.version.clone(),
+                registry: self.registry.to_string(),
+                available,
+            });
+            if available {
                 return Ok(());
             }
             // Check the timeout after every registry probe.
             if Instant::now().duration_since(start) > timeout {
-                eyre::bail!(
-                    "exceeded timeout of {:?} waiting for crate {} {} to be published",
-                    timeout,
-                    self.inner.name,
-                    self.inner.version.to_string()
-                );
+                return Err(self.availability_timeout(timeout));
             }
         }
     }
 
+    fn publish_fatal(&self, error: PublishError, stderr: &str) -> Error {
+        Error::PublishFatal {
+            package: self.inner.name.to_string(),
+            version: self.inner.version.clone(),
+            code: error.code().copied(),
+            failure: error.failure(),
+            stderr: stderr.to_string(),
+        }
+    }
+
+    fn availability_timeout(&self, timeout: Duration) -> Error {
+        Error::AvailabilityTimeout {
+            package: self.inner.name.to_string(),
+            version: self.inner.version.clone(),
+            timeout,
+        }
+    }
+
+    /// Returns the delay before retrying a failed attempt.
+    ///
+    /// Registries that announce when they accept the next upload are retried exactly then, other
+    /// failures back off according to `policy`.
+    fn retry_delay(
+        &self,
+        stderr: &str,
+        class: RetryClass,
+        attempt: usize,
+        policy: &RetryPolicy,
+    ) -> Duration {
+        let now = std::time::SystemTime::now();
+        let Some(wait_duration) =
+            retry::retry_after_message(stderr).and_then(|at| at.duration_since(now).ok())
+        else {
+            return policy.jittered(policy.delay(class, attempt));
+        };
+        log::warning!(
+            self.log,
+            "[{}@{}] {} asks to retry after {}",
+            self.inner.name,
+            self.inner.version,
+            self.registry,
+            humantime::format_rfc3339_seconds(now + wait_duration),
+        );
+        wait_duration
+    }
+
+    /// Logs a failed attempt that will be retried.
+    fn warn_retryable(&self, error: PublishError) {
+        match (error.code(), error.failure()) {
+            (Some(code), _) => log::warning!(
+                self.log,
+                "[{}@{}] intermittent failure: {} {}",
+                self.inner.name,
+                self.inner.version,
+                code.as_u16(),
+                code.canonical_reason().unwrap_or_default(),
+            ),
+            (None, Some(failure)) => log::warning!(
+                self.log,
+                "[{}@{}] intermittent failure: {failure}",
+                self.inner.name,
+                self.inner.version,
+            ),
+            (None, None) => log::warning!(
+                self.log,
+                "[{}@{}] unknown failure",
+                self.inner.name,
+                self.inner.version,
+            ),
+        }
+    }
+
     pub async fn attempt_publish(
         &self,
         mut cmd: async_process::Command,
         max_retries: usize,
-    ) -> eyre::Result<()> {
+        policy: &RetryPolicy,
+        events: &Events,
+    ) -> Result<()> {
         let mut attempt = 0;
+        let mut waited = Duration::ZERO;
         loop {
             attempt += 1;
+            self.stats.lock().attempts = attempt;
 
             if attempt > 1 {
-                action::warning!(
+                log::warning!(
+                    self.log,
                     "[{}@{}] publishing (attempt {}/{})",
                     self.inner.name,
                     self.inner.version,
@@ -250,70 +565,62 @@ impl Package {
             let output = cmd.output().await?;
             let stdout = String::from_utf8_lossy(&output.stdout);
             let stderr = String::from_utf8_lossy(&output.stderr);
-            action::debug!("{}", &stdout);
-            action::debug!("{}", &stderr);
+            log::debug!(self.log, "{}", &stdout);
+            log::debug!(self.log, "{}", &stderr);
 
             if output.status.success() {
                 return Ok(());
             }
-            action::warning!("{}", &stdout);
-            action::warning!("{}", &stderr);
-
-            // Treat manifest verification failures as fatal so we don't retry
-            // for hours on static configuration problems.
-            if stderr.contains(
-                "all dependencies must have a version requirement specified when publishing.",
-            ) {
-                eyre::bail!(
-                    "command {:?} failed due to manifest verification error: {}",
-                    cmd,
-                    stderr
-                );
-            }
+            log::warning!(self.log, "{}", &stdout);
+            log::warning!(self.log, "{}", &stderr);
 
-            if stderr.contains("already exists on crates.io index") {
+            let error = classify_publish_error(&stderr);
+            if error.category() == FailureCategory::AlreadyPublished {
+                log::info!(
+                    self.log,
+                    "[{}@{}] already published",
+                    self.inner.name,
+                    self.inner.version
+                );
                 return Ok(());
             }
-
-            let error = classify_publish_error(&stderr);
-            let wait_duration = match error {
-                PublishError::Fatal(_) => {
-                    eyre::bail!("command {:?} failed: {}", cmd, stderr);
-                }
-                PublishError::Retryable(code) => {
-                    action::warning!(
-                        "[{}@{}] intermittent failure: {} {}",
-                        self.inner.name,
-                        self.inner.version,
-                        code.as_u16(),
-                        code.canonical_reason().unwrap_or_default(),
-                    );
-                    match code {
-                        http::StatusCode::TOO_MANY_REQUESTS => {
-                            // Rate limits back off for ten minutes to respect registry throttling.
-                            std::time::Duration::from_mins(10)
-                        }
-                        // Other retryable failures use a five-minute backoff.
-                        _ => std::time::Duration::from_mins(5),
-                    }
-                }
-                PublishError::Unknown => {
-                    action::warning!(
-                        "[{}@{}] unknown failure",
-                        self.inner.name,
-                        self.inner.version,
-                    );
-                    // Unknown failures use a five-minute backoff before another attempt.
-                    std::time::Duration::from_mins(5)
-                }
+            self.stats.lock().error = Some(error);
+            events.emit(&Event::AttemptFailed {
+                name: self.inner.name.to_string(),
+                version: self.inner.version.clone(),
+                attempt,
+                error,
+                message: stderr.to_string(),
+            });
+            let Some(class) = RetryClass::of(&error) else {
+                return Err(self.publish_fatal(error, &stderr));
             };
+            self.warn_retryable(error);
 
             if attempt > max_retries {
-                eyre::bail!("command {:?} failed: {}", cmd, stderr);
+                return Err(Error::RetriesExhausted {
+                    package: self.inner.name.to_string(),
+                    version: self.inner.version.clone(),
+                    attempts: attempt,
+                    stderr: stderr.to_string(),
+                });
+            }
+            let wait_duration = self.retry_delay(&stderr, class, attempt, policy);
+            waited += wait_duration;
+            if let Some(budget) = policy.budget
+                && waited > budget
+            {
+                return Err(Error::RetryBudgetExhausted {
+                    package: self.inner.name.to_string(),
+                    version: self.inner.version.clone(),
+                    budget,
+                    stderr: stderr.to_string(),
+                });
             }
 
             let next_attempt = std::time::SystemTime::now() + wait_duration;
-            action::warning!(
+            log::warning!(
+                self.log,
                 "[{}@{}] attempting again in {wait_duration:?} at {}",
                 self.inner.name,
                 self.inner.version,
@@ -321,77 +628,140 @@ impl Package {
                     .format(DATETIME_FORMAT)
                     .unwrap_or_else(|_| humantime::format_rfc3339(next_attempt).to_string())
             );
+            events.emit(&Event::RetryScheduled {
+                name: self.inner.name.to_string(),
+                version: self.inner.version.clone(),
+                attempt: attempt + 1,
+                delay_secs: wait_duration.as_secs(),
+                at: humantime::format_rfc3339_seconds(next_attempt).to_string(),
+            });
             sleep(wait_duration).await;
         }
     }
 
-    /// Publishes this package after all local dependencies are available.
-    pub async fn publish(self: Arc<Self>, options: Arc<Options>) -> eyre::Result<Arc<Self>> {
-        use async_process::Command;
-
-        action::info!("[{}@{}] publishing", self.inner.name, self.inner.version);
-
-        let mut cmd = Command::new("cargo");
+    /// Builds the `cargo publish` command for this package.
+    fn publish_command(&self, options: &Options) -> async_process::Command {
+        let mut cmd = async_process::Command::new("cargo");
         cmd.arg("publish");
-
-        if options.no_verify {
-            cmd.arg("--no-verify");
-        }
         cmd.current_dir(&self.path);
-        if let Some(ref token) = options.registry_token {
-            cmd.env("CARGO_REGISTRY_TOKEN", token);
+        // Stop the upload when the run is dropped, for example on Ctrl-C.
+        cmd.kill_on_drop(true);
+        if let Some(token) = &self.registry_token {
+            cmd.env(self.registry.token_env_var(), token);
         }
         if options.dry_run {
             cmd.arg("--dry-run");
-            // Local package versions are unavailable on crates.io during a dry-run.
-            if options.resolve_versions && !self.deps.read().is_empty() {
-                // Skip Cargo's dry-run because its dependency lookup would always fail.
-                action::info!(
-                    "[{}@{}] dry-run: proceed without `cargo publish --dry-run` due to resolve version incompatibility",
-                    self.inner.name,
-                    self.inner.version
-                );
-                *self.published.lock() = true;
-                return Ok(self);
-            }
         }
-        if options.resolve_versions {
+        if options.resolve_versions.unwrap_or_default() {
             // Resolved versions intentionally modify Cargo.toml before publishing.
             cmd.arg("--allow-dirty");
         }
-        cmd.args(&options.extra_args);
+        let mut args = self.config.publish_args(options);
+        // Route the package to the registry it was resolved to, such as one of `package.publish`.
+        if let Some(registry) = self.registry.cargo_name()
+            && argument_value(&args, "--registry").is_none()
+            && argument_value(&args, "--index").is_none()
+        {
+            args.extend(["--registry".to_string(), registry.to_string()]);
+        }
+        cmd.args(args);
+        cmd
+    }
+
+    /// Publishes this package after all local dependencies are available.
+    ///
+    /// `max_retries` applies unless the package configures its own.
+    pub async fn publish(
+        self: Arc<Self>,
+        options: Arc<Options>,
+        max_retries: usize,
+        store: Arc<StateStore>,
+        events: Arc<Events>,
+    ) -> Result<Arc<Self>> {
+        use async_process::Command;
+
+        log::info!(
+            self.log,
+            "[{}@{}] publishing",
+            self.inner.name,
+            self.inner.version
+        );
+        self.set_state(&store, PackageState::Publishing);
+        events.emit(&Event::PublishStarted {
+            name: self.inner.name.to_string(),
+            version: self.inner.version.clone(),
+        });
+
+        // Local package versions are unavailable on crates.io during a dry-run.
+        if options.dry_run
+            && options.resolve_versions.unwrap_or_default()
+            && !self.deps.read().is_empty()
+        {
+            // Skip Cargo's dry-run because its dependency lookup would always fail.
+            log::info!(
+                self.log,
+                "[{}@{}] dry-run: proceed without `cargo publish --dry-run` due to resolve version incompatibility",
+                self.inner.name,
+                self.inner.version
+            );
+            self.mark_published(&store, &events);
+            return Ok(self);
+        }
+        let cmd = self.publish_command(&options);
 
-        let max_retries = options.max_retries.unwrap_or(10);
-        self.attempt_publish(cmd, max_retries).await?;
+        let max_retries = self.config.max_retries.unwrap_or(max_retries);
+        let upload_start = Instant::now();
+        let uploaded = self
+            .attempt_publish(cmd, max_retries, &options.retry_policy, &events)
+            .await;
+        self.stats.lock().upload = Some(upload_start.elapsed());
+        uploaded?;
 
         if options.dry_run {
-            action::info!(
+            log::info!(
+                self.log,
                 "[{}@{}] dry-run: skip waiting for successful publish",
                 &self.inner.name,
                 self.inner.version
             );
-            *self.published.lock() = true;
+            self.mark_published(&store, &events);
             return Ok(self);
         }
+        self.set_state(&store, PackageState::Uploaded);
+        events.emit(&Event::UploadDone {
+            name: self.inner.name.to_string(),
+            version: self.inner.version.clone(),
+        });
 
         // Dependants can publish only after the registry serves this exact version.
-        self.wait_package_available(None).await?;
-
-        let publish_delay = options
+        let wait_start = Instant::now();
+        let available = self.wait_package_available(None, &events).await;
+        self.stats.lock().availability_wait = Some(wait_start.elapsed());
+        available?;
+        self.set_state(&store, PackageState::Available);
+
+        let publish_delay = self
+            .config
             .publish_delay
+            .or(options.publish_delay)
             .unwrap_or_else(|| Duration::from_secs(30));
         sleep(publish_delay).await;
+        self.stats.lock().delay = Some(publish_delay);
 
         let mut cmd = Command::new("cargo");
         cmd.arg("update");
         cmd.current_dir(&self.path);
+        cmd.kill_on_drop(true);
         let output = cmd.output().await?;
         if !output.status.success() {
-            eyre::bail!("command {:?} failed", cmd);
+            return Err(Error::Command {
+                command: format!("{cmd:?}"),
+            });
         }
 
-        *self.published.lock() = true;
-        action::info!(
+        self.mark_published(&store, &events);
+        log::info!(
+            self.log,
             "[{}@{}] published successfully",
             self.inner.name,
             self.inner.version
@@ -401,50 +771,152 @@ impl Package {
     }
 }
 
-type TaskFut = dyn Future<Output = eyre::Result<Arc<Package>>>;
+type TaskFut = dyn Future<Output = Result<Arc<Package>>>;
 
-fn find_packages(
-    metadata: &cargo_metadata::Metadata,
+/// A workspace package with the registry it publishes to, before its registry client exists.
+struct ResolvedPackage<'a> {
+    inner: &'a cargo_metadata::Package,
+    publishable: bool,
+    skip_reason: Option<SkipReason>,
+    config: PackageConfig,
+    registry: Registry,
+}
+
+/// Resolves the registry of every workspace package that is published or required.
+fn resolve_packages<'a>(
+    metadata: &'a cargo_metadata::Metadata,
     options: &Options,
-) -> impl Iterator<Item = (PathBuf, Arc<Package>)> {
-    let packages = metadata.workspace_packages();
-    packages.into_iter().filter_map(move |package| {
-        let publishable = package.publish.as_ref().is_none_or(|p| !p.is_empty());
+    selection: &Selection,
+    config: &CargoConfig,
+) -> Result<Vec<ResolvedPackage<'a>>> {
+    let mut resolved = Vec::new();
+    for package in metadata.workspace_packages() {
+        let publishable = is_publishable(package);
+
+        let skip_reason = if publishable {
+            selection.skip_reason(&package.name)
+        } else {
+            Some(SkipReason::NotPublishable)
+        };
+        let should_publish = skip_reason.is_none();
+
+        // Packages that are never published do not need a resolvable registry, unless a selected
+        // package depends on them.
+        let is_required = selection.required_by(&package.name).is_some();
+        let package_config = PackageConfig::of(package)?;
+        let registry = if should_publish || is_required {
+            let args = package_config.publish_args(options);
+            Registry::resolve(package, &args, package_config.registry.as_deref(), config)?
+        } else {
+            Registry::CratesIo
+        };
+        resolved.push(ResolvedPackage {
+            inner: package,
+            publishable,
+            skip_reason,
+            config: package_config,
+            registry,
+        });
+    }
+    Ok(resolved)
+}
 
-        let is_included = options
-            .include
-            .as_ref()
-            .is_none_or(|inc| inc.is_empty() || inc.contains(&package.name));
+/// Returns the registry that [`Options::registry_token`] authenticates, if it is set.
+///
+/// The token belongs to the single registry that packages are published to. It is never reused
+/// for other registries, which authenticate with their own tokens from the environment or Cargo
+/// credentials.
+fn registry_token_target(
+    packages: &[ResolvedPackage],
+    options: &Options,
+) -> Result<Option<Registry>> {
+    if options.registry_token.is_none() {
+        return Ok(None);
+    }
+    let targets = packages
+        .iter()
+        .filter(|package| package.skip_reason.is_none())
+        .map(|package| &package.registry)
+        .collect::<HashSet<_>>();
+    match targets.into_iter().collect::<Vec<_>>()[..] {
+        [] => Ok(None),
+        [registry] => Ok(Some(registry.clone())),
+        ref registries => {
+            let mut registries = registries
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            registries.sort();
+            Err(Error::AmbiguousRegistryToken { registries })
+        }
+    }
+}
 
-        let is_excluded = options
-            .exclude
-            .as_ref()
-            .is_some_and(|excl| excl.contains(&package.name));
+fn find_packages(
+    metadata: &cargo_metadata::Metadata,
+    options: &Options,
+    selection: &Selection,
+) -> Result<HashMap<PathBuf, Arc<Package>>> {
+    let config = CargoConfig::discover(metadata.workspace_root.as_std_path())?;
+    let resolved = resolve_packages(metadata, options, selection, &config)?;
+    let token_target = registry_token_target(&resolved, options)?;
+    let token = |registry: &Registry| match &token_target {
+        Some(target) if target == registry => options.registry_token.clone(),
+        _ => None,
+    };
 
-        let should_publish = publishable && is_included && !is_excluded;
+    let git_index_cache = metadata
+        .target_directory
+        .join("publish-crates")
+        .join("git-index");
+    // Share one client per registry so caches and rate limits apply across packages.
+    let mut clients: HashMap<Registry, Arc<dyn RegistryClient>> = HashMap::new();
+    let mut packages = HashMap::new();
+    for package in resolved {
+        let registry = package.registry;
+        let registry_client = match (&options.registry_client, clients.get(&registry)) {
+            (Some(client), _) | (None, Some(client)) => Arc::clone(client),
+            (None, None) => {
+                let client_token = token(&registry).or_else(|| config.token(&registry));
+                let client =
+                    registry.client(client_token.as_deref(), git_index_cache.as_std_path())?;
+                clients.insert(registry.clone(), Arc::clone(&client));
+                client
+            }
+        };
 
-        let path: PathBuf = package.manifest_path.parent()?.into();
-        Some((
+        let Some(path) = package.inner.manifest_path.parent() else {
+            continue;
+        };
+        let path: PathBuf = path.into();
+        packages.insert(
             path.clone(),
             Arc::new(Package {
-                inner: package.clone(),
+                inner: package.inner.clone(),
                 path,
-                publishable,
-                should_publish,
+                publishable: package.publishable,
+                skip_reason: package.skip_reason,
+                config: package.config,
+                registry_token: token(&registry),
+                registry,
+                registry_client,
+                log: options.log(),
                 published: Mutex::new(false),
+                stats: Mutex::new(PackageStats::default()),
                 deps: RwLock::new(HashMap::new()),
                 dependants: RwLock::new(HashMap::new()),
             }),
-        ))
-    })
+        );
+    }
+    Ok(packages)
 }
 
 fn update_dependency_version(
     manifest: &mut toml_edit::DocumentMut,
-    package_name: &str,
+    manifest_path: &Path,
     dependency: &cargo_metadata::Dependency,
     version: &semver::VersionReq,
-) -> eyre::Result<bool> {
+) -> Result<bool> {
     use toml_edit::value;
 
     let section = dependency_section(dependency);
@@ -453,15 +925,21 @@ fn update_dependency_version(
     };
 
     let dependency_key = dependency.rename.as_deref().unwrap_or(&dependency.name);
-    let manifest_dependency = manifest_dependency_mut(manifest, dependency).ok_or_else(|| {
-        eyre::eyre!("{package_name}: dependency {dependency_key} is missing from {section}",)
-    })?;
-    {
-        let dependency_table = manifest_dependency.as_table_like_mut().ok_or_else(|| {
-            eyre::eyre!(
-                "{package_name}: dependency {dependency_key} does not use a detailed manifest entry",
-            )
+    let manifest_dependency =
+        manifest_dependency_mut(manifest, dependency).ok_or_else(|| Error::ManifestEdit {
+            path: manifest_path.to_path_buf(),
+            message: format!("dependency {dependency_key} is missing from {section}"),
         })?;
+    {
+        let dependency_table =
+            manifest_dependency
+                .as_table_like_mut()
+                .ok_or_else(|| Error::ManifestEdit {
+                    path: manifest_path.to_path_buf(),
+                    message: format!(
+                        "dependency {dependency_key} does not use a detailed manifest entry"
+                    ),
+                })?;
         dependency_table.insert("version", value(version.to_string()));
     }
     if let Some(inline_table) = manifest_dependency.as_inline_table_mut() {
@@ -484,7 +962,7 @@ fn dependency_section(dependency: &cargo_metadata::Dependency) -> Option<&'stati
 ///
 /// Keys are compared as parsed platforms because Cargo accepts arbitrary spacing inside
 /// `cfg(...)` expressions, so the manifest text may differ from the canonical rendering.
-fn target_table_key(manifest: &toml_edit::DocumentMut, target: &Platform) -> Option<String> {
+fn target_table_key(manifest: &toml_edit::Table, target: &Platform) -> Option<String> {
     let targets = manifest.get("target")?.as_table_like()?;
     targets
         .iter()
@@ -494,7 +972,7 @@ fn target_table_key(manifest: &toml_edit::DocumentMut, target: &Platform) -> Opt
 }
 
 fn manifest_dependency<'a>(
-    manifest: &'a toml_edit::DocumentMut,
+    manifest: &'a toml_edit::Table,
     dependency: &cargo_metadata::Dependency,
 ) -> Option<&'a toml_edit::Item> {
     let section = dependency_section(dependency)?;
@@ -546,15 +1024,27 @@ struct DependencyDeclaration {
     inherits_workspace: bool,
 }
 
+fn workspace_dependency<'a>(
+    workspace_manifest: &'a toml_edit::Table,
+    dependency_key: &str,
+) -> Option<&'a toml_edit::Item> {
+    workspace_manifest
+        .get("workspace")
+        .and_then(|workspace| workspace.get("dependencies"))
+        .and_then(toml_edit::Item::as_table_like)
+        .and_then(|dependencies| dependencies.get(dependency_key))
+}
+
 fn dependency_declaration(
-    manifest: &toml_edit::DocumentMut,
-    workspace_manifest: &toml_edit::DocumentMut,
-    package_name: &str,
+    manifest: &toml_edit::Table,
+    workspace_manifest: &toml_edit::Table,
+    manifest_path: &Path,
     dependency: &cargo_metadata::Dependency,
-) -> eyre::Result<DependencyDeclaration> {
+) -> Result<DependencyDeclaration> {
     let dependency_key = dependency.rename.as_deref().unwrap_or(&dependency.name);
-    let item = manifest_dependency(manifest, dependency).ok_or_else(|| {
-        eyre::eyre!("{package_name}: dependency {dependency_key} is missing from its manifest")
+    let item = manifest_dependency(manifest, dependency).ok_or_else(|| Error::ManifestEdit {
+        path: manifest_path.to_path_buf(),
+        message: format!("dependency {dependency_key} is missing"),
     })?;
 
     let inherits_workspace = item
@@ -573,15 +1063,12 @@ fn dependency_declaration(
         });
     }
 
-    let workspace_dependency = workspace_manifest
-        .get("workspace")
-        .and_then(|workspace| workspace.get("dependencies"))
-        .and_then(toml_edit::Item::as_table_like)
-        .and_then(|dependencies| dependencies.get(dependency_key))
-        .ok_or_else(|| {
-            eyre::eyre!(
-                "{package_name}: workspace dependency {dependency_key} is missing from the workspace manifest"
-            )
+    let workspace_dependency = workspace_dependency(workspace_manifest, dependency_key)
+        .ok_or_else(|| Error::ManifestEdit {
+            path: manifest_path.to_path_buf(),
+            message: format!(
+                "workspace dependency {dependency_key} is missing from the workspace manifest"
+            ),
         })?;
 
     let has_version = workspace_dependency.is_str()
@@ -594,30 +1081,134 @@ fn dependency_declaration(
     })
 }
 
+/// The workspace manifest, parsed with spans to locate its dependency declarations.
+struct WorkspaceManifest {
+    path: cargo_metadata::camino::Utf8PathBuf,
+    document: toml_edit::Document<String>,
+}
+
+/// Returns `path:line` of the version requirement in `item`, which was parsed from `raw`.
+///
+/// Falls back to the path alone if the declaration cannot be located.
+fn requirement_location(
+    path: &cargo_metadata::camino::Utf8Path,
+    raw: &str,
+    item: Option<&toml_edit::Item>,
+) -> String {
+    let line = item
+        .map(|item| {
+            item.as_table_like()
+                .and_then(|dependency| dependency.get("version"))
+                .unwrap_or(item)
+        })
+        .and_then(toml_edit::Item::span)
+        .and_then(|span| raw.get(..span.start))
+        .map(|before| before.matches('\n').count() + 1);
+    match line {
+        Some(line) => format!("{path}:{line}"),
+        None => path.to_string(),
+    }
+}
+
+/// Describes a local requirement of `package` that does not match the version of `resolved`.
+///
+/// `original` is the manifest of `package`, which declares the requirement unless it is inherited
+/// from the workspace.
+fn requirement_mismatch(
+    package: &Package,
+    dependency: &cargo_metadata::Dependency,
+    resolved: &Package,
+    declaration: &DependencyDeclaration,
+    workspace_manifest: &WorkspaceManifest,
+    original: &str,
+) -> String {
+    let location = if declaration.inherits_workspace {
+        let dependency_key = dependency.rename.as_deref().unwrap_or(&dependency.name);
+        requirement_location(
+            &workspace_manifest.path,
+            workspace_manifest.document.raw(),
+            workspace_dependency(&workspace_manifest.document, dependency_key),
+        )
+    } else {
+        let spanned = toml_edit::Document::parse(original).ok();
+        requirement_location(
+            &package.inner.manifest_path,
+            original,
+            spanned
+                .as_ref()
+                .and_then(|spanned| manifest_dependency(spanned, dependency)),
+        )
+    };
+    format!(
+        "{location}: {} requires {} {} but the workspace has {} {}",
+        &package.inner.name,
+        &dependency.name,
+        &dependency.req,
+        &resolved.inner.name,
+        &resolved.inner.version,
+    )
+}
+
+/// Fails unless the local dependency `resolved` of `package` will be published in this run or the
+/// registry already has its version.
+fn ensure_dependency_available(
+    package: &Package,
+    dependency: &str,
+    resolved: &Package,
+) -> Result<()> {
+    if !resolved.publishable {
+        return Err(Error::UnpublishableDependency {
+            package: package.inner.name.to_string(),
+            dependency: dependency.to_string(),
+        });
+    }
+    if !resolved.should_publish() && !resolved.published() {
+        return Err(Error::ExcludedDependency {
+            package: package.inner.name.to_string(),
+            dependency: dependency.to_string(),
+            version: resolved.inner.version.clone(),
+            registry: resolved.registry.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Validates and records the local dependencies of `package`.
+///
+/// Returns the version requirements of local dependencies that do not match the workspace
+/// version of the dependency, so that all mismatches can be reported at once.
 async fn prepare_package(
     package: &Arc<Package>,
-    workspace_manifest: &toml_edit::DocumentMut,
+    workspace_manifest: &WorkspaceManifest,
     packages: &HashMap<PathBuf, Arc<Package>>,
     options: &Options,
-) -> eyre::Result<()> {
+) -> Result<Vec<String>> {
     use toml_edit::DocumentMut;
-    let manifest = tokio::fs::read_to_string(&package.inner.manifest_path).await?;
-    let mut manifest = manifest.parse::<DocumentMut>()?;
+    let manifest_path = package.inner.manifest_path.as_std_path();
+    let original = tokio::fs::read_to_string(manifest_path).await?;
+    let mut manifest = original
+        .parse::<DocumentMut>()
+        .map_err(|error| Error::ManifestParse {
+            path: manifest_path.to_path_buf(),
+            error,
+        })?;
     let mut need_update = false;
+    let mut mismatches = Vec::new();
 
     for dependency in &package.inner.dependencies {
         let mut dependency_version = dependency.req.clone();
         if let Some(path) = dependency.path.as_ref().map(PathBuf::from) {
             // Resolve every local dependency, even if it already has a version requirement.
-            let resolved = packages.get(&path).ok_or(eyre::eyre!(
-                "{}: could not resolve local dependency {}",
-                &package.inner.name,
-                path.display()
-            ))?;
+            let resolved = packages
+                .get(&path)
+                .ok_or_else(|| Error::UnresolvedLocalDependency {
+                    package: package.inner.name.to_string(),
+                    path: path.clone(),
+                })?;
             let declaration = dependency_declaration(
                 &manifest,
-                workspace_manifest,
-                &package.inner.name,
+                &workspace_manifest.document,
+                manifest_path,
                 dependency,
             )?;
 
@@ -630,40 +1221,48 @@ async fn prepare_package(
                 continue;
             }
 
-            // A published package cannot depend on a local package excluded from this run.
-            if !resolved.should_publish {
-                eyre::bail!(
-                    "{}: cannot publish because dependency {} will not be published",
-                    &package.inner.name,
-                    &dependency.name,
-                );
+            ensure_dependency_available(package, &dependency.name, resolved)?;
+
+            if !options.resolve_versions.unwrap_or_default()
+                && declaration.has_version
+                && !dependency.req.matches(&resolved.inner.version)
+            {
+                mismatches.push(requirement_mismatch(
+                    package,
+                    dependency,
+                    resolved,
+                    &declaration,
+                    workspace_manifest,
+                    &original,
+                ));
             }
 
-            if options.resolve_versions {
+            if options.resolve_versions.unwrap_or_default() {
                 // Use the version declared by the package at the dependency path.
-                dependency_version = format!("={}", resolved.inner.version).parse()?;
+                let existing = declaration.has_version.then_some(&dependency.req);
+                dependency_version = options
+                    .version_req_style
+                    .unwrap_or_default()
+                    .requirement(&resolved.inner.version, existing)
+                    .map_err(|err| Error::Dependency {
+                        package: package.inner.name.to_string(),
+                        dependency: dependency.name.clone(),
+                        source: Box::new(err),
+                    })?;
 
                 let changed = dependency_version != dependency.req;
                 if changed && !declaration.inherits_workspace {
                     // Keep the manifest aligned with the graph used for publishing.
                     need_update |= update_dependency_version(
                         &mut manifest,
-                        &package.inner.name,
+                        manifest_path,
                         dependency,
                         &dependency_version,
                     )?;
                 }
             }
 
-            package
-                .deps
-                .write()
-                .insert(resolved.inner.name.to_string(), resolved.clone());
-
-            resolved
-                .dependants
-                .write()
-                .insert(package.inner.name.to_string(), package.clone());
+            package.add_local_dependency(resolved, dependency.kind);
         }
 
         let is_dev_dependency = dependency.kind == DependencyKind::Development;
@@ -671,46 +1270,45 @@ async fn prepare_package(
         let is_missing_exact_version = dependency_version == semver::VersionReq::STAR;
 
         if is_missing_exact_version && is_non_local_dependency {
-            return Err(eyre::eyre!(
-                "{}: dependency {} has no specific version ({})",
-                &package.inner.name,
-                &dependency.name,
-                dependency_version
-            ).suggestion("to automatically resolve versions of local workspace members, use '--resolve-versions'"));
+            return Err(Error::MissingVersion {
+                package: package.inner.name.to_string(),
+                dependency: dependency.name.clone(),
+                requirement: dependency_version,
+            });
         }
     }
 
     // Dry-runs validate the edits in memory without modifying the checkout.
     if !options.dry_run && need_update {
-        use tokio::io::AsyncWriteExt;
-        action::debug!("{}", &manifest.to_string());
-        action::info!(
+        log::debug!(options.log(), "{}", &manifest.to_string());
+        log::info!(
+            options.log(),
             "[{}@{}] updating {}",
             package.inner.name,
             package.inner.version,
             package.inner.manifest_path
         );
-        let mut file = tokio::fs::OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(&package.inner.manifest_path)
-            .await?;
-        file.write_all(manifest.to_string().as_bytes()).await?;
-        file.flush().await?;
+        write_manifest(manifest_path, &original, &manifest.to_string()).await?;
     }
 
-    Ok(())
+    Ok(mismatches)
 }
 
 async fn build_dag(
     metadata: &cargo_metadata::Metadata,
     packages: &HashMap<PathBuf, Arc<Package>>,
     options: &Options,
-) -> eyre::Result<()> {
-    let workspace_manifest = tokio::fs::read_to_string(metadata.workspace_root.join("Cargo.toml"))
-        .await?
-        .parse::<toml_edit::DocumentMut>()?;
-    let packages_iter = packages.values().filter(|package| package.should_publish);
+) -> Result<()> {
+    let path = metadata.workspace_root.join("Cargo.toml");
+    let document =
+        toml_edit::Document::parse(tokio::fs::read_to_string(&path).await?).map_err(|error| {
+            Error::ManifestParse {
+                path: path.clone().into(),
+                error,
+            }
+        })?;
+    let workspace_manifest = WorkspaceManifest { path, document };
+    let packages_iter = packages.values().filter(|package| package.should_publish());
     let results: Vec<_> = stream::iter(packages_iter)
         .map(|package| prepare_package(package, &workspace_manifest, packages, options))
         .buffer_unordered(8)
@@ -718,33 +1316,220 @@ async fn build_dag(
         .await;
 
     // Report any package error only after the bounded concurrent validation finishes.
-    results.into_iter().collect::<eyre::Result<Vec<_>>>()?;
-    Ok(())
+    let mut mismatches = results
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    if !mismatches.is_empty() {
+        mismatches.sort();
+        return Err(Error::RequirementMismatch { mismatches });
+    }
+    check_cycles(packages)
 }
 
-/// Updates local path dependency versions in `[workspace.dependencies]`.
+/// Finds a cycle of local dependency edges, returned as the packages along it.
 ///
-/// This is required for newer Cargo versions which enforce that any
-/// published dependency that has a `path` also specifies an explicit
-/// version requirement. For workspaces that rely on `[workspace.dependencies]`
-/// plus `foo.workspace = true` in member manifests, the version must be set
-/// on the workspace-level dependency.
-async fn update_workspace_dependencies(
-    metadata: &cargo_metadata::Metadata,
-    packages: &HashMap<PathBuf, Arc<Package>>,
-    options: &Options,
-) -> eyre::Result<bool> {
-    use toml_edit::{DocumentMut, Item, Value, value};
-
-    // Fast path: nothing to do when resolve_versions is disabled.
-    if !options.resolve_versions {
-        return Ok(false);
+/// The first package is repeated at the end, e.g. `[a, b, a]`.
+fn find_cycle(packages: &HashMap<PathBuf, Arc<Package>>) -> Option<Vec<Arc<Package>>> {
+    fn visit(
+        package: &Arc<Package>,
+        stack: &mut Vec<Arc<Package>>,
+        visited: &mut HashSet<String>,
+    ) -> Option<Vec<Arc<Package>>> {
+        let name = package.inner.name.to_string();
+        if let Some(start) = stack
+            .iter()
+            .position(|p| p.inner.name == package.inner.name)
+        {
+            let mut cycle = stack.split_off(start);
+            cycle.push(package.clone());
+            return Some(cycle);
+        }
+        if !visited.insert(name) {
+            return None;
+        }
+        let mut deps = package
+            .deps
+            .read()
+            .values()
+            .map(|dep| dep.package.clone())
+            .collect::<Vec<_>>();
+        deps.sort_by(|a, b| a.inner.name.cmp(&b.inner.name));
+
+        stack.push(package.clone());
+        for dep in &deps {
+            if let Some(cycle) = visit(dep, stack, visited) {
+                return Some(cycle);
+            }
+        }
+        stack.pop();
+        None
     }
 
-    let workspace_manifest_path = metadata.workspace_root.join("Cargo.toml");
-    let manifest = tokio::fs::read_to_string(&workspace_manifest_path).await?;
-    let mut manifest = manifest.parse::<DocumentMut>()?;
-    let mut need_update = false;
+    // Visit packages by name so the reported cycle is stable across runs.
+    let mut roots = packages.values().collect::<Vec<_>>();
+    roots.sort_by(|a, b| a.inner.name.cmp(&b.inner.name));
+    let mut visited = HashSet::new();
+    roots
+        .into_iter()
+        .find_map(|package| visit(package, &mut Vec::new(), &mut visited))
+}
+
+/// Fails when local dependencies form a cycle, since no package of the cycle could ever be ready.
+fn check_cycles(packages: &HashMap<PathBuf, Arc<Package>>) -> Result<()> {
+    let Some(cycle) = find_cycle(packages) else {
+        return Ok(());
+    };
+    let names = cycle
+        .iter()
+        .map(|package| package.inner.name.to_string())
+        .collect::<Vec<_>>();
+    let mut edges = Vec::new();
+    let mut dev_edges = Vec::new();
+    for pair in cycle.windows(2) {
+        let [from, to] = pair else { continue };
+        let kinds = from
+            .deps
+            .read()
+            .get(to.inner.name.as_str())
+            .map(|dep| dep.kinds.clone())
+            .unwrap_or_default();
+        if kinds == [DependencyKind::Development] {
+            dev_edges.push(format!("{} on {}", from.inner.name, to.inner.name));
+        }
+        let kinds = kinds
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        edges.push(format!(
+            "{} -> {} ({kinds})",
+            from.inner.name, to.inner.name
+        ));
+    }
+
+    Err(Error::Cycle {
+        packages: names,
+        edges,
+        dev_edges,
+    })
+}
+
+/// Returns the requirement to write for a workspace dependency, or [`None`] if `existing` is
+/// already resolved.
+fn workspace_requirement(
+    name: &str,
+    version: &semver::Version,
+    existing: Option<&str>,
+    options: &Options,
+) -> Result<Option<String>> {
+    let existing = existing.map(str::parse::<semver::VersionReq>).transpose()?;
+    let ver_req = options
+        .version_req_style
+        .unwrap_or_default()
+        .requirement(version, existing.as_ref())
+        .map_err(|err| Error::Dependency {
+            package: "workspace".to_string(),
+            dependency: name.to_string(),
+            source: Box::new(err),
+        })?;
+    let has_resolved_version = existing.as_ref() == Some(&ver_req);
+    Ok((!has_resolved_version).then(|| ver_req.to_string()))
+}
+
+/// Rewrites the version requirements of local entries in `[workspace.dependencies]`.
+///
+/// `requirement` is called with the package name, the dependency key and the existing
+/// requirement of every detailed entry with a `path`, and returns the requirement to write, if
+/// any. Returns `true` if an entry was rewritten.
+fn rewrite_workspace_dependencies(
+    manifest: &mut toml_edit::DocumentMut,
+    mut requirement: impl FnMut(&str, &str, Option<&str>) -> Result<Option<String>>,
+) -> Result<bool> {
+    use toml_edit::{Item, Value, value};
+
+    let Some(table) = manifest
+        .get_mut("workspace")
+        .and_then(|workspace| workspace.get_mut("dependencies"))
+        .and_then(Item::as_table_mut)
+    else {
+        return Ok(false);
+    };
+
+    let mut need_update = false;
+    for (name, item) in table.iter_mut() {
+        let dep_name = name.get().to_string();
+        let package_name = item
+            .as_inline_table()
+            .and_then(|dependency| dependency.get("package"))
+            .and_then(Value::as_str)
+            .or_else(|| {
+                item.as_table()
+                    .and_then(|dependency| dependency.get("package"))
+                    .and_then(Item::as_str)
+            })
+            .unwrap_or(&dep_name)
+            .to_string();
+
+        // Handle both inline tables and normal tables, skipping simple string dependencies like
+        // `foo = "1"`.
+        if let Some(inline) = item.as_inline_table_mut() {
+            if inline.get("path").is_none() {
+                continue;
+            }
+            let existing = inline.get("version").and_then(Value::as_str);
+            let Some(ver_req) = requirement(&package_name, &dep_name, existing)? else {
+                continue;
+            };
+
+            inline.insert("version", Value::from(ver_req));
+            inline.fmt();
+            need_update = true;
+        } else if let Some(table_item) = item.as_table_mut() {
+            if table_item.get("path").is_none() {
+                continue;
+            }
+            let existing = table_item.get("version").and_then(Item::as_str);
+            let Some(ver_req) = requirement(&package_name, &dep_name, existing)? else {
+                continue;
+            };
+
+            table_item["version"] = value(ver_req);
+            need_update = true;
+        }
+    }
+    Ok(need_update)
+}
+
+/// Updates local path dependency versions in `[workspace.dependencies]`.
+///
+/// This is required for newer Cargo versions which enforce that any
+/// published dependency that has a `path` also specifies an explicit
+/// version requirement. For workspaces that rely on `[workspace.dependencies]`
+/// plus `foo.workspace = true` in member manifests, the version must be set
+/// on the workspace-level dependency.
+async fn update_workspace_dependencies(
+    metadata: &cargo_metadata::Metadata,
+    packages: &HashMap<PathBuf, Arc<Package>>,
+    options: &Options,
+) -> Result<bool> {
+    use toml_edit::DocumentMut;
+
+    // Fast path: nothing to do when resolve_versions is disabled.
+    if !options.resolve_versions.unwrap_or_default() {
+        return Ok(false);
+    }
+
+    let workspace_manifest_path = metadata.workspace_root.join("Cargo.toml");
+    let original = tokio::fs::read_to_string(&workspace_manifest_path).await?;
+    let mut manifest = original
+        .parse::<DocumentMut>()
+        .map_err(|error| Error::ManifestParse {
+            path: workspace_manifest_path.clone().into(),
+            error,
+        })?;
 
     // Index package names once because every workspace dependency may need a lookup.
     let mut name_to_pkg: HashMap<String, &Arc<Package>> = HashMap::new();
@@ -754,7 +1539,7 @@ async fn update_workspace_dependencies(
 
     let required_versions = packages
         .values()
-        .filter(|package| package.should_publish)
+        .filter(|package| package.should_publish())
         .flat_map(|package| &package.inner.dependencies)
         .filter(|dependency| {
             let Some(path) = dependency.path.as_ref().map(PathBuf::from) else {
@@ -771,144 +1556,421 @@ async fn update_workspace_dependencies(
         .map(|dependency| dependency.name.as_str())
         .collect::<HashSet<_>>();
 
-    if let Some(workspace) = manifest.get_mut("workspace")
-        && let Some(deps_item) = workspace.get_mut("dependencies")
-        && let Some(table) = deps_item.as_table_mut()
-    {
-        for (name, item) in table.iter_mut() {
-            // Only consider workspace dependencies that correspond
-            // to local workspace members.
-            let dep_name = name.get().to_string();
-            let package_name = item
-                .as_inline_table()
-                .and_then(|dependency| dependency.get("package"))
-                .and_then(Value::as_str)
-                .or_else(|| {
-                    item.as_table()
-                        .and_then(|dependency| dependency.get("package"))
-                        .and_then(Item::as_str)
-                })
-                .unwrap_or(&dep_name)
-                .to_string();
-            let Some(pkg) = name_to_pkg.get(&package_name) else {
-                continue;
+    let need_update =
+        rewrite_workspace_dependencies(&mut manifest, |package_name, dep_name, existing| {
+            let Some(pkg) = name_to_pkg.get(package_name) else {
+                return Ok(None);
             };
-            if !required_versions.contains(package_name.as_str()) {
-                continue;
-            }
-
-            // Skip simple string dependencies like `foo = "1"`.
-            if item.is_str() {
-                continue;
-            }
-
-            // Handle both inline tables and normal tables.
-            if let Some(inline) = item.as_inline_table_mut() {
-                let has_path = inline.get("path").is_some();
-                let ver_req = format!("={}", pkg.inner.version);
-                let has_resolved_version =
-                    inline.get("version").and_then(Value::as_str) == Some(ver_req.as_str());
-
-                if !has_path || has_resolved_version {
-                    continue;
-                }
-
-                inline.insert("version", Value::from(ver_req));
-                inline.fmt();
-                need_update = true;
-            } else if let Some(table_item) = item.as_table_mut() {
-                let has_path = table_item.get("path").is_some();
-                let ver_req = format!("={}", pkg.inner.version);
-                let has_resolved_version =
-                    table_item.get("version").and_then(Item::as_str) == Some(ver_req.as_str());
-
-                if !has_path || has_resolved_version {
-                    continue;
-                }
-
-                table_item["version"] = value(ver_req);
-                need_update = true;
+            if !required_versions.contains(package_name) {
+                return Ok(None);
             }
-        }
-    }
+            workspace_requirement(dep_name, &pkg.inner.version, existing, options)
+        })?;
 
     // Persist changes to the workspace manifest.
     if !options.dry_run && need_update {
-        use tokio::io::AsyncWriteExt;
-        action::debug!("{}", &manifest.to_string());
-        action::info!(
+        log::debug!(options.log(), "{}", &manifest.to_string());
+        log::info!(
+            options.log(),
             "updating workspace manifest {}",
             workspace_manifest_path.as_str()
         );
-        let mut f = tokio::fs::OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(&workspace_manifest_path)
-            .await?;
-        f.write_all(manifest.to_string().as_bytes()).await?;
-        f.flush().await?;
+        write_manifest(
+            workspace_manifest_path.as_std_path(),
+            &original,
+            &manifest.to_string(),
+        )
+        .await?;
     }
 
     Ok(need_update)
 }
 
-/// Publishes selected workspace packages to crates.io in dependency order.
+/// Marks selected packages whose exact version already exists on their registry as published.
 ///
-/// Local path dependencies form the publishing graph. Independent packages run concurrently up to
-/// [`Options::concurrency_limit`], while each dependant waits for its dependencies to appear on the
-/// registry.
+/// Rerunning a partially failed release then skips the verify build of every package that was
+/// already uploaded. Registry errors are reported as warnings and leave the package to
+/// `cargo publish`, which rejects duplicate versions itself.
+async fn skip_published_packages(packages: &HashMap<PathBuf, Arc<Package>>, store: &StateStore) {
+    let selected = packages
+        .values()
+        .filter(|package| package.should_publish() && !package.published());
+    stream::iter(selected)
+        .map(|package| async move {
+            let exists = package
+                .registry_client
+                .version_exists(&package.inner.name, &package.inner.version)
+                .await;
+            match exists {
+                Ok(true) => {
+                    log::info!(
+                        package.log,
+                        "[{}@{}] already published, skipping",
+                        package.inner.name,
+                        package.inner.version
+                    );
+                    *package.published.lock() = true;
+                    package.stats.lock().outcome =
+                        Some(Outcome::Skipped(SkipReason::AlreadyPublished));
+                    package.set_state(store, PackageState::Done);
+                }
+                Ok(false) => package.set_state(store, PackageState::Pending),
+                Err(err) => {
+                    log::warning!(
+                        package.log,
+                        "[{}@{}] failed to check whether the version is already published: {}",
+                        package.inner.name,
+                        package.inner.version,
+                        err
+                    );
+                }
+            }
+        })
+        .buffer_unordered(8)
+        .collect::<Vec<()>>()
+        .await;
+}
+
+/// Treats deselected packages that selected packages depend on as published if their registry
+/// already has their version, so their dependants can proceed.
 ///
-/// Versionless development dependencies on private packages are excluded because Cargo omits them
-/// from the published manifest.
+/// Dependencies whose version is missing remain unpublished and are reported by `build_dag`.
+async fn verify_required_dependencies(
+    packages: &HashMap<PathBuf, Arc<Package>>,
+    selection: &Selection,
+) {
+    let required = packages.values().filter_map(|package| {
+        let dependant = selection.required_by(&package.inner.name)?;
+        (!package.should_publish()).then_some((package, dependant))
+    });
+    stream::iter(required)
+        .map(|(package, dependant)| async move {
+            let exists = package
+                .registry_client
+                .version_exists(&package.inner.name, &package.inner.version)
+                .await;
+            match exists {
+                Ok(true) => {
+                    log::info!(package.log,
+                        "[{}@{}] not selected, but already published to {} as required by {dependant}",
+                        package.inner.name,
+                        package.inner.version,
+                        package.registry,
+                    );
+                    *package.published.lock() = true;
+                }
+                Ok(false) => {}
+                Err(err) => {
+                    log::warning!(package.log,
+                        "[{}@{}] failed to check whether the version required by {dependant} is published: {err}",
+                        package.inner.name,
+                        package.inner.version,
+                    );
+                }
+            }
+        })
+        .buffer_unordered(8)
+        .collect::<Vec<()>>()
+        .await;
+}
+
+/// Returns the state file used by [`Options::resume`] when [`Options::state_file`] is unset.
+fn default_state_file(metadata: &cargo_metadata::Metadata) -> PathBuf {
+    metadata
+        .target_directory
+        .join("publish-crates")
+        .join("state.json")
+        .into()
+}
+
+/// Opens the state file selected by the options, or an in-memory store without one.
+fn open_state_store(metadata: &cargo_metadata::Metadata, options: &Options) -> Result<StateStore> {
+    let state_file = options
+        .state_file
+        .clone()
+        .or_else(|| options.resume.then(|| default_state_file(metadata)));
+    match state_file {
+        Some(path) if options.dry_run => {
+            log::info!(
+                options.log(),
+                "dry-run: not using state file {}",
+                path.display()
+            );
+            Ok(StateStore::in_memory())
+        }
+        Some(path) => {
+            log::info!(
+                options.log(),
+                "recording publish state in {}",
+                path.display()
+            );
+            StateStore::open(path, options.resume)
+        }
+        None => Ok(StateStore::in_memory()),
+    }
+}
+
+/// Treats packages uploaded by a previous run as published once the registry serves them.
 ///
-/// # Errors
+/// Packages that do not become available are left to be published again.
+async fn resume_packages(
+    packages: &HashMap<PathBuf, Arc<Package>>,
+    store: &StateStore,
+    events: &Events,
+) {
+    let uploaded = packages.values().filter(|package| {
+        package.should_publish()
+            && store
+                .previous(&package.inner.name, &package.inner.version)
+                .is_some_and(|state| state >= PackageState::Uploaded)
+    });
+    stream::iter(uploaded)
+        .map(|package| async move {
+            log::info!(
+                package.log,
+                "[{}@{}] resuming: uploaded by a previous run, verifying availability",
+                package.inner.name,
+                package.inner.version
+            );
+            match package.wait_package_available(None, events).await {
+                Ok(()) => package.mark_published(store, events),
+                Err(err) => {
+                    log::warning!(
+                        package.log,
+                        "[{}@{}] resuming: {}; publishing again",
+                        package.inner.name,
+                        package.inner.version,
+                        err
+                    );
+                }
+            }
+        })
+        .buffer_unordered(8)
+        .collect::<Vec<()>>()
+        .await;
+}
+
+/// Discovers workspace packages, resolves local dependency versions and builds the publish graph.
 ///
-/// Returns an error when the options or Cargo metadata are invalid, dependency versions cannot be
-/// resolved, a selected package depends on an excluded package, `cargo publish` fails permanently,
-/// or a published package does not become available before the registry timeout.
-pub async fn publish(mut options: Options) -> eyre::Result<()> {
-    options.validate()?;
-    action::info!("searching cargo packages at {}", options.path.display());
-
-    let manifest_path = if options.path.is_file() {
-        options.path.clone()
-    } else {
-        options.path.join("Cargo.toml")
-    };
+/// With `snapshots`, every file that [`Options::resolve_versions`] or publishing may modify is
+/// recorded before the first modification.
+async fn load_packages(
+    options: &Options,
+    snapshots: Option<&ManifestSnapshots>,
+) -> Result<(cargo_metadata::Metadata, HashMap<PathBuf, Arc<Package>>)> {
+    log::info!(
+        options.log(),
+        "searching cargo packages at {}",
+        options.path.display()
+    );
+
+    let manifest_path = options.manifest_path();
+    // Mismatched local requirements are reported by `build_dag`, so skip dependency resolution.
     let mut metadata = cargo_metadata::MetadataCommand::new()
         .manifest_path(&manifest_path)
+        .no_deps()
         .exec()?;
 
-    let mut packages: HashMap<PathBuf, Arc<Package>> = find_packages(&metadata, &options).collect();
+    if let Some(snapshots) = snapshots {
+        let workspace_root = metadata.workspace_root.as_std_path();
+        snapshots.record(&workspace_root.join("Cargo.toml"))?;
+        snapshots.record(&workspace_root.join("Cargo.lock"))?;
+        for package in metadata.workspace_packages() {
+            snapshots.record(package.manifest_path.as_std_path())?;
+        }
+    }
+
+    let changed = match &options.changed_since {
+        Some(git_ref) => {
+            let changed = changes::changed_since(
+                &metadata,
+                git_ref,
+                options.include_dependants.unwrap_or_default(),
+            )
+            .await?;
+            log::info!(
+                options.log(),
+                "packages changed since {git_ref}: {changed:?}"
+            );
+            Some(changed)
+        }
+        None => None,
+    };
+    let selection = Selection::new(&metadata, options, changed.as_ref())?;
+    let mut packages = find_packages(&metadata, options, &selection)?;
     // For workspaces using `[workspace.dependencies]`, ensure local path
     // dependencies have explicit versions before we start publishing.
-    let workspace_changed = update_workspace_dependencies(&metadata, &packages, &options).await?;
+    let workspace_changed = update_workspace_dependencies(&metadata, &packages, options).await?;
     if workspace_changed && !options.dry_run {
         // Cargo metadata retains the old requirements, so reload it after manifest mutation.
         metadata = cargo_metadata::MetadataCommand::new()
             .manifest_path(&manifest_path)
+            .no_deps()
             .exec()?;
-        packages = find_packages(&metadata, &options).collect();
+        packages = find_packages(&metadata, options, &selection)?;
     }
-    build_dag(&metadata, &packages, &options).await?;
+    verify_required_dependencies(&packages, &selection).await;
+    build_dag(&metadata, &packages, options).await?;
 
-    action::info!(
+    log::info!(
+        options.log(),
         "found packages: {:?}",
         packages
             .values()
             .map(|p| p.inner.name.clone())
             .collect::<Vec<_>>()
     );
+    Ok((metadata, packages))
+}
+
+/// Publishes selected workspace packages to their target registries in dependency order.
+///
+/// Local path dependencies form the publishing graph. Independent packages run concurrently up to
+/// [`Options::concurrency_limit`], while each dependant waits for its dependencies to appear on the
+/// registry.
+///
+/// Versionless development dependencies on private packages are excluded because Cargo omits them
+/// from the published manifest. Packages whose exact version already exists on their registry are
+/// skipped without running `cargo publish`.
+///
+/// # Errors
+///
+/// Returns an error when the options, configuration or Cargo metadata are invalid, dependency
+/// versions cannot be resolved, a selected package depends on an excluded package, local
+/// dependencies form a cycle, `cargo publish` fails permanently, or a published package does not
+/// become available before the registry timeout. Errors after publishing started are wrapped in
+/// [`Error::Failed`] with the [`PublishReport`] up to the failure; use [`Error::inner`] to match
+/// on the error that stopped publishing and [`Error::report`] to read the report.
+pub async fn publish(options: Options) -> Result<PublishReport> {
+    let options = options.with_workspace_config()?;
+    let events = Arc::new(Events::new(
+        options.message_format,
+        options.observer.clone(),
+    ));
+    publish_with_events(options, events).await
+}
+
+/// Runs [`publish`] and reports its progress to `events`, ending with [`Event::RunFinished`].
+async fn publish_with_events(options: Options, events: Arc<Events>) -> Result<PublishReport> {
+    let interrupt = options.interrupt.clone();
+    let result = if options.restore_manifests.unwrap_or_default() {
+        publish_restoring_manifests(options, Arc::clone(&events), interrupt.as_deref()).await
+    } else {
+        until_interrupted(
+            interrupt.as_deref(),
+            publish_packages(options, Arc::clone(&events), None),
+        )
+        .await
+    };
+    events.emit(&Event::RunFinished {
+        success: result.is_ok(),
+        error: result.as_ref().err().map(ToString::to_string),
+    });
+    result
+}
+
+/// Runs `run` until it finishes or `interrupt` is notified.
+async fn until_interrupted(
+    interrupt: Option<&tokio::sync::Notify>,
+    run: impl Future<Output = Result<PublishReport>>,
+) -> Result<PublishReport> {
+    let Some(interrupt) = interrupt else {
+        return run.await;
+    };
+    tokio::select! {
+        result = run => result,
+        // Dropping the run stops scheduling and kills running Cargo processes, which are spawned
+        // with `kill_on_drop`.
+        () = interrupt.notified() => Err(Error::Interrupted),
+    }
+}
+
+/// Runs [`publish_packages`] and restores the snapshotted files afterwards, including when
+/// interrupted through [`Options::interrupt`].
+async fn publish_restoring_manifests(
+    options: Options,
+    events: Arc<Events>,
+    interrupt: Option<&tokio::sync::Notify>,
+) -> Result<PublishReport> {
+    let log = options.log();
+    let snapshots = ManifestSnapshots::default();
+    let result = until_interrupted(
+        interrupt,
+        publish_packages(options, events, Some(&snapshots)),
+    )
+    .await;
+    if matches!(result, Err(Error::Interrupted)) {
+        log::warning!(log, "interrupted, restoring manifests");
+    }
+    let restored = snapshots.restore(log).await;
+    match (result, restored) {
+        (result, Ok(())) => result,
+        (Ok(report), Err(err)) => Err(Error::Failed {
+            error: Box::new(err),
+            report: Box::new(report),
+        }),
+        // Files that failed to restore were logged, so report the error that stopped publishing.
+        (Err(err), Err(_)) => Err(err),
+    }
+}
+
+/// Publishes the packages of validated `options`, see [`Options::with_workspace_config`].
+async fn publish_packages(
+    options: Options,
+    events: Arc<Events>,
+    snapshots: Option<&ManifestSnapshots>,
+) -> Result<PublishReport> {
+    let (metadata, packages) = load_packages(&options, snapshots).await?;
+
+    let store = Arc::new(open_state_store(&metadata, &options)?);
+    if options.resume {
+        resume_packages(&packages, &store, &events).await;
+    }
+    skip_published_packages(&packages, &store).await;
+
+    let plan = plan::build(&packages, options.concurrency_limit.unwrap_or(4))?;
+    let skipped = plan.skipped.clone();
+    events.emit(&Event::Plan(plan));
+    for package in skipped {
+        events.emit(&Event::Skipped {
+            name: package.name,
+            version: package.version,
+            skip_reason: package.reason,
+        });
+    }
 
-    options.max_retries = Some(options.max_retries.unwrap_or(2 * packages.len()));
+    let max_retries = options.max_retries.unwrap_or(2 * packages.len());
     let options = Arc::new(options);
 
+    let result = schedule_packages(&packages, options, max_retries, store, events).await;
+    let report = PublishReport::new(&packages);
+    match result {
+        Ok(()) => Ok(report),
+        Err(err) => Err(Error::Failed {
+            error: Box::new(err),
+            report: Box::new(report),
+        }),
+    }
+}
+
+/// Publishes packages as their dependencies become available, up to the concurrency limit.
+async fn schedule_packages(
+    packages: &HashMap<PathBuf, Arc<Package>>,
+    options: Arc<Options>,
+    max_retries: usize,
+    store: Arc<StateStore>,
+    events: Arc<Events>,
+) -> Result<()> {
     if packages.is_empty() {
         // Fast path: nothing to publish.
         return Ok(());
     }
-    let mut ready: VecDeque<Arc<Package>> =
-        packages.values().filter(|p| p.ready()).cloned().collect();
+    // Already published packages never run, but their dependants start out ready.
+    let mut ready: VecDeque<Arc<Package>> = packages
+        .values()
+        .filter(|p| p.ready() && !p.published())
+        .cloned()
+        .collect();
 
     let mut tasks: FuturesUnordered<Pin<Box<TaskFut>>> = FuturesUnordered::new();
 
@@ -929,18 +1991,33 @@ pub async fn publish(mut options: Options) -> eyre::Result<()> {
             };
             // Package selection
             match ready.pop_front() {
-                Some(p) if !p.should_publish => {
-                    action::info!(
-                        "[{}@{}] skipping (publish=false)",
+                Some(p) if let Some(reason) = p.skip_reason => {
+                    log::info!(
+                        p.log,
+                        "[{}@{}] skipping ({})",
                         p.inner.name,
-                        p.inner.version
+                        p.inner.version,
+                        reason
                     );
                 }
                 Some(p) => {
                     tasks.push({
                         let options = Arc::clone(&options);
+                        let store = Arc::clone(&store);
+                        let events = Arc::clone(&events);
                         Box::pin(async move {
-                            let res = p.publish(options).await;
+                            let package = Arc::clone(&p);
+                            let res = p
+                                .publish(options, max_retries, store, Arc::clone(&events))
+                                .await;
+                            if let Err(err) = &res {
+                                package.stats.lock().outcome = Some(Outcome::Failed);
+                                events.emit(&Event::Failed {
+                                    name: package.inner.name.to_string(),
+                                    version: package.inner.version.clone(),
+                                    error: err.to_string(),
+                                });
+                            }
 
                             // Release the concurrency slot before reporting completion.
                             drop(permit);
@@ -955,9 +2032,7 @@ pub async fn publish(mut options: Options) -> eyre::Result<()> {
 
         // Completed package
         match tasks.next().await {
-            Some(Err(err)) => {
-                eyre::bail!("a task failed: {}", err)
-            }
+            Some(Err(err)) => return Err(err),
             Some(Ok(completed)) => {
                 // Newly unblocked dependants
                 ready.extend(
@@ -973,106 +2048,52 @@ pub async fn publish(mut options: Options) -> eyre::Result<()> {
         }
     }
 
-    if !packages
+    let mut unpublished = packages
         .values()
-        .all(|p| !p.should_publish || p.published())
-    {
-        eyre::bail!("not all published");
+        .filter(|p| p.should_publish() && !p.published())
+        .map(|p| p.inner.name.to_string())
+        .collect::<Vec<_>>();
+    if !unpublished.is_empty() {
+        unpublished.sort();
+        return Err(Error::NotAllPublished {
+            packages: unpublished,
+        });
     }
 
     Ok(())
 }
 
-/// Classification of publishing errors.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum PublishError {
-    /// An error without a recognized HTTP status code.
-    Unknown,
-    /// A transient HTTP error that may succeed when retried.
-    Retryable(http::StatusCode),
-    /// An HTTP error that should not be retried.
-    Fatal(http::StatusCode),
-}
-
-impl PublishError {
-    /// Returns the recognized HTTP status code, if one was found.
-    #[must_use]
-    pub fn code(&self) -> Option<&http::StatusCode> {
-        match self {
-            Self::Unknown => None,
-            Self::Retryable(code) | Self::Fatal(code) => Some(code),
-        }
-    }
-}
-
-/// Classifies a `cargo publish` error from HTTP status text.
-///
-/// This approach assumes that the error messages of `cargo publish` include network errors
-/// in the form `<code> <canonical_reason>`.
-///
-/// Classification:
-///
-/// - [`PublishError::Retryable`] for temporary or intermittent errors.
-/// - [`PublishError::Fatal`] for permanent errors such as missing permissions.
-/// - [`PublishError::Unknown`] when no recognized status text is present.
-fn classify_publish_error(text: &str) -> PublishError {
-    for code_num in 100u16..=599 {
-        let Ok(code) = http::StatusCode::from_u16(code_num) else {
-            continue;
-        };
-        let Some(reason) = code.canonical_reason() else {
-            continue;
-        };
-
-        let needle = format!("{} {}", code.as_str(), reason);
-        if text.contains(&needle) {
-            if code.is_redirection()
-                || code.is_server_error()
-                || code == http::StatusCode::NOT_FOUND
-                || code == http::StatusCode::REQUEST_TIMEOUT
-                || code == http::StatusCode::CONFLICT
-                || code == http::StatusCode::GONE
-                || code == http::StatusCode::PRECONDITION_FAILED
-                || code == http::StatusCode::RANGE_NOT_SATISFIABLE
-                || code == http::StatusCode::EXPECTATION_FAILED
-                || code == http::StatusCode::MISDIRECTED_REQUEST
-                || code == http::StatusCode::UNPROCESSABLE_ENTITY
-                || code == http::StatusCode::LOCKED
-                || code == http::StatusCode::FAILED_DEPENDENCY
-                || code == http::StatusCode::TOO_EARLY
-                || code == http::StatusCode::UPGRADE_REQUIRED
-                || code == http::StatusCode::PRECONDITION_REQUIRED
-                || code == http::StatusCode::TOO_MANY_REQUESTS
-                || code == http::StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS
-            {
-                return PublishError::Retryable(code);
-            }
-            return PublishError::Fatal(code);
-        }
-    }
-
-    PublishError::Unknown
-}
-
 #[cfg(test)]
 mod tests {
     use std::path::{Path, PathBuf};
 
     use similar_asserts::assert_eq as sim_assert_eq;
 
-    fn options(path: PathBuf) -> super::Options {
+    pub(crate) fn options(path: PathBuf) -> super::Options {
         super::Options {
             path,
             registry_token: None,
             dry_run: false,
             publish_delay: None,
-            no_verify: false,
-            resolve_versions: false,
+            no_verify: None,
+            resolve_versions: None,
+            version_req_style: None,
             include: None,
             exclude: None,
+            include_dependencies: None,
+            changed_since: None,
+            include_dependants: None,
             max_retries: None,
+            retry_policy: super::RetryPolicy::default(),
             concurrency_limit: None,
             extra_args: Vec::new(),
+            registry_client: None,
+            state_file: None,
+            resume: false,
+            message_format: super::MessageFormat::Human,
+            observer: None,
+            restore_manifests: None,
+            interrupt: None,
         }
     }
 
@@ -1096,86 +2117,988 @@ edition = "2021"
         std::fs::write(package_dir.join("src/lib.rs"), "").expect("package source must be written");
     }
 
+    /// Initializes a git repository at `dir` and commits all files.
+    fn commit_all(dir: &Path) {
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(dir)
+                .output()
+                .expect("git must run")
+                .status;
+            assert!(status.success(), "git {args:?} must succeed");
+        };
+        git(&["init", "--quiet"]);
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "initial"]);
+    }
+
     fn package_map(
         metadata: &cargo_metadata::Metadata,
         options: &super::Options,
     ) -> std::collections::HashMap<PathBuf, std::sync::Arc<super::Package>> {
-        super::find_packages(metadata, options).collect()
+        let selection = super::Selection::new(metadata, options, None)
+            .expect("workspace packages must be selected");
+        super::find_packages(metadata, options, &selection)
+            .expect("workspace packages must resolve")
     }
 
     #[test]
-    fn classifies_retryable_fatal_and_unknown_publish_errors() {
-        let cases = [
-            (
-                "the remote server responded with 429 Too Many Requests",
-                super::PublishError::Retryable(http::StatusCode::TOO_MANY_REQUESTS),
-            ),
-            (
-                "the remote server responded with 500 Internal Server Error",
-                super::PublishError::Retryable(http::StatusCode::INTERNAL_SERVER_ERROR),
-            ),
-            (
-                "the remote server responded with 403 Forbidden",
-                super::PublishError::Fatal(http::StatusCode::FORBIDDEN),
-            ),
-            (
-                "the remote server responded with an unknown error",
-                super::PublishError::Unknown,
-            ),
-        ];
+    fn rejects_zero_concurrency_limit() {
+        let mut options = options(PathBuf::from("Cargo.toml"));
+        options.concurrency_limit = Some(0);
+
+        let error = options
+            .validate()
+            .expect_err("zero concurrency must be rejected");
+
+        sim_assert_eq!(
+            error.to_string(),
+            "concurrency limit must be greater than zero"
+        );
+    }
+
+    #[test]
+    fn package_selection_applies_exclusions_after_inclusions() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/foo", "crates/bar"]
+resolver = "2"
+"#,
+        )
+        .expect("workspace manifest must be written");
+        write_member(temp.path(), "foo", "");
+        write_member(temp.path(), "bar", "");
+
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(&workspace_manifest_path)
+            .exec()
+            .expect("workspace metadata must load");
+        let mut options = options(workspace_manifest_path);
+        options.include = Some(vec!["foo".to_string(), "bar".to_string()]);
+        options.exclude = Some(vec!["bar".to_string()]);
+
+        let selected = package_map(&metadata, &options)
+            .values()
+            .map(|package| (package.inner.name.to_string(), package.should_publish()))
+            .collect::<std::collections::HashMap<_, _>>();
+
+        sim_assert_eq!(selected.get("foo"), Some(&true));
+        sim_assert_eq!(selected.get("bar"), Some(&false));
+    }
+
+    /// Routes availability checks through an injected registry client.
+    #[tokio::test]
+    async fn injected_registry_client_reports_availability() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/foo"]
+resolver = "2"
+"#,
+        )
+        .expect("workspace manifest must be written");
+        write_member(temp.path(), "foo", "");
+
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(&workspace_manifest_path)
+            .exec()
+            .expect("workspace metadata must load");
+        let registry = std::sync::Arc::new(super::InMemoryRegistry::new());
+        let mut options = options(workspace_manifest_path);
+        options.registry_client = Some(registry.clone());
+        let packages = package_map(&metadata, &options);
+        let foo = packages
+            .values()
+            .find(|package| package.inner.name == "foo")
+            .expect("foo package must be present");
+
+        assert!(!foo.is_available().await.expect("registry must answer"));
+        registry.insert("foo", semver::Version::new(1, 2, 3));
+        assert!(foo.is_available().await.expect("registry must answer"));
+        registry.yank("foo", &semver::Version::new(1, 2, 3));
+        assert!(!foo.is_available().await.expect("registry must answer"));
+    }
+
+    /// Skips `cargo publish` for every package whose version is already on the registry.
+    #[tokio::test]
+    async fn publish_skips_already_published_packages() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/foo", "crates/consumer"]
+resolver = "2"
+"#,
+        )
+        .expect("workspace manifest must be written");
+        write_member(temp.path(), "foo", "");
+        write_member(
+            temp.path(),
+            "consumer",
+            r#"[dependencies]
+foo = { path = "../foo", version = "1.2.3" }
+"#,
+        );
+
+        let registry = std::sync::Arc::new(super::InMemoryRegistry::new());
+        registry.insert("foo", semver::Version::new(1, 2, 3));
+        registry.insert("consumer", semver::Version::new(1, 2, 3));
+        let mut options = options(workspace_manifest_path);
+        options.registry_client = Some(registry);
+
+        // Publishing would fail without registry credentials, so success proves it was skipped.
+        let report = super::publish(options)
+            .await
+            .expect("already published packages must be skipped");
+
+        sim_assert_eq!(
+            report
+                .packages
+                .iter()
+                .map(|package| (package.name.as_str(), package.outcome, package.attempts))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "consumer",
+                    super::Outcome::Skipped(super::SkipReason::AlreadyPublished),
+                    0
+                ),
+                (
+                    "foo",
+                    super::Outcome::Skipped(super::SkipReason::AlreadyPublished),
+                    0
+                ),
+            ]
+        );
+    }
+
+    /// Restores manifests rewritten by `resolve_versions` once the run finishes.
+    #[tokio::test]
+    async fn publish_restores_resolved_manifests() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/foo", "crates/consumer"]
+resolver = "2"
+"#,
+        )
+        .expect("workspace manifest must be written");
+        write_member(temp.path(), "foo", "");
+        write_member(
+            temp.path(),
+            "consumer",
+            r#"[dependencies]
+foo = { path = "../foo" }
+"#,
+        );
+        let consumer_manifest_path = temp
+            .path()
+            .join("crates")
+            .join("consumer")
+            .join("Cargo.toml");
+        let original =
+            std::fs::read_to_string(&consumer_manifest_path).expect("manifest must be readable");
+
+        let registry = std::sync::Arc::new(super::InMemoryRegistry::new());
+        registry.insert("foo", semver::Version::new(1, 2, 3));
+        registry.insert("consumer", semver::Version::new(1, 2, 3));
+        let mut options = options(workspace_manifest_path);
+        options.registry_client = Some(registry);
+        options.resolve_versions = Some(true);
+        options.restore_manifests = Some(true);
+
+        super::publish(options)
+            .await
+            .expect("already published packages must be skipped");
+
+        sim_assert_eq!(
+            std::fs::read_to_string(&consumer_manifest_path).expect("manifest must be readable"),
+            original
+        );
+    }
+
+    /// Interrupts publishing once the first package starts publishing.
+    #[derive(Debug)]
+    struct InterruptOnStart(std::sync::Arc<tokio::sync::Notify>);
+
+    impl super::PublishObserver for InterruptOnStart {
+        fn on_start(&self, _name: &str, _version: &semver::Version) {
+            self.0.notify_one();
+        }
+    }
+
+    /// Stops publishing when interrupted and restores the resolved manifests.
+    #[tokio::test]
+    async fn publish_stops_when_interrupted() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/foo", "crates/consumer"]
+resolver = "2"
+"#,
+        )
+        .expect("workspace manifest must be written");
+        write_member(temp.path(), "foo", "");
+        write_member(
+            temp.path(),
+            "consumer",
+            r#"[dependencies]
+foo = { path = "../foo" }
+"#,
+        );
+        let consumer_manifest_path = temp
+            .path()
+            .join("crates")
+            .join("consumer")
+            .join("Cargo.toml");
+        let original =
+            std::fs::read_to_string(&consumer_manifest_path).expect("manifest must be readable");
+
+        let interrupt = std::sync::Arc::new(tokio::sync::Notify::new());
+        let mut options = options(workspace_manifest_path);
+        options.registry_client = Some(std::sync::Arc::new(super::InMemoryRegistry::new()));
+        options.dry_run = true;
+        options.resolve_versions = Some(true);
+        options.restore_manifests = Some(true);
+        options.observer = Some(std::sync::Arc::new(InterruptOnStart(
+            std::sync::Arc::clone(&interrupt),
+        )));
+        options.interrupt = Some(interrupt);
+
+        let error = super::publish(options)
+            .await
+            .expect_err("interrupted publishing must fail");
+        assert!(matches!(error, super::Error::Interrupted), "{error:?}");
+        sim_assert_eq!(
+            std::fs::read_to_string(&consumer_manifest_path).expect("manifest must be readable"),
+            original
+        );
+    }
+
+    /// Records observer notifications.
+    #[derive(Debug, Default)]
+    struct RecordingObserver(parking_lot::Mutex<Vec<String>>);
+
+    impl super::PublishObserver for RecordingObserver {
+        fn on_plan(&self, plan: &super::Plan) {
+            self.0
+                .lock()
+                .push(format!("plan: {} waves", plan.waves.len()));
+        }
+
+        fn on_skipped(&self, name: &str, version: &semver::Version, reason: super::SkipReason) {
+            self.0
+                .lock()
+                .push(format!("skipped: {name}@{version} ({reason})"));
+        }
+    }
+
+    /// Notifies an injected observer without enabling JSON output.
+    #[tokio::test]
+    async fn publish_notifies_observer() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/foo", "crates/internal"]
+resolver = "2"
+"#,
+        )
+        .expect("workspace manifest must be written");
+        write_member(temp.path(), "foo", "");
+        write_member(temp.path(), "internal", "publish = false\n");
+
+        let registry = std::sync::Arc::new(super::InMemoryRegistry::new());
+        registry.insert("foo", semver::Version::new(1, 2, 3));
+        let observer = std::sync::Arc::new(RecordingObserver::default());
+        let mut options = options(workspace_manifest_path);
+        options.registry_client = Some(registry);
+        options.observer = Some(observer.clone());
+
+        super::publish(options)
+            .await
+            .expect("already published packages must be skipped");
+
+        sim_assert_eq!(
+            observer.0.lock().clone(),
+            vec![
+                "plan: 0 waves".to_string(),
+                "skipped: foo@1.2.3 (already published)".to_string(),
+                "skipped: internal@1.2.3 (publish = false)".to_string(),
+            ]
+        );
+    }
+
+    /// Collects JSON events written by a publish run.
+    #[derive(Clone, Default)]
+    struct EventBuffer(std::sync::Arc<parking_lot::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for EventBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Emits the plan, skipped packages and the run result as newline-delimited JSON.
+    #[tokio::test]
+    async fn publish_emits_json_events() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/foo", "crates/internal"]
+resolver = "2"
+"#,
+        )
+        .expect("workspace manifest must be written");
+        write_member(temp.path(), "foo", "");
+        write_member(temp.path(), "internal", "publish = false\n");
+
+        let registry = std::sync::Arc::new(super::InMemoryRegistry::new());
+        registry.insert("foo", semver::Version::new(1, 2, 3));
+        let mut options = options(workspace_manifest_path);
+        options.registry_client = Some(registry);
+
+        let buffer = EventBuffer::default();
+        let events =
+            super::Events::to_writer(super::MessageFormat::Json, Box::new(buffer.clone()), None);
+        super::publish_with_events(options, std::sync::Arc::new(events))
+            .await
+            .expect("already published packages must be skipped");
+
+        let output = String::from_utf8(buffer.0.lock().clone()).expect("events must be UTF-8");
+        let events = output
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("every line must be a JSON event"))
+            .collect::<Vec<serde_json::Value>>();
+        sim_assert_eq!(
+            events,
+            vec![
+                serde_json::json!({
+                    "reason": "plan",
+                    "waves": [],
+                    "skipped": [
+                        {"name": "foo", "version": "1.2.3", "reason": "already_published"},
+                        {"name": "internal", "version": "1.2.3", "reason": "not_publishable"},
+                    ],
+                }),
+                serde_json::json!({
+                    "reason": "skipped",
+                    "name": "foo",
+                    "version": "1.2.3",
+                    "skip_reason": "already_published",
+                }),
+                serde_json::json!({
+                    "reason": "skipped",
+                    "name": "internal",
+                    "version": "1.2.3",
+                    "skip_reason": "not_publishable",
+                }),
+                serde_json::json!({"reason": "run_finished", "success": true, "error": null}),
+            ]
+        );
+    }
+
+    /// Resumes packages uploaded by a previous run and records the final states.
+    #[tokio::test]
+    async fn resume_marks_previously_uploaded_packages_as_published() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/foo", "crates/bar"]
+resolver = "2"
+"#,
+        )
+        .expect("workspace manifest must be written");
+        write_member(temp.path(), "foo", "");
+        write_member(temp.path(), "bar", "");
+        let state_file = temp.path().join("state.json");
+        std::fs::write(
+            &state_file,
+            r#"{"packages": {
+                "foo": {"version": "1.2.3", "state": "uploaded"},
+                "bar": {"version": "1.2.3", "state": "publishing"}
+            }}"#,
+        )
+        .expect("state file must be written");
+
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(&workspace_manifest_path)
+            .exec()
+            .expect("workspace metadata must load");
+        let registry = std::sync::Arc::new(super::InMemoryRegistry::new());
+        registry.insert("foo", semver::Version::new(1, 2, 3));
+        let mut options = options(workspace_manifest_path);
+        options.registry_client = Some(registry);
+        options.state_file = Some(state_file.clone());
+        options.resume = true;
+        let packages = package_map(&metadata, &options);
+        let store = super::open_state_store(&metadata, &options).expect("state file must load");
+
+        let events = super::Events::new(super::MessageFormat::Human, None);
+        super::resume_packages(&packages, &store, &events).await;
+
+        let published = packages
+            .values()
+            .map(|package| (package.inner.name.to_string(), package.published()))
+            .collect::<std::collections::HashMap<_, _>>();
+        sim_assert_eq!(published.get("foo"), Some(&true));
+        sim_assert_eq!(published.get("bar"), Some(&false));
+        let state = std::fs::read_to_string(&state_file).expect("state file must be readable");
+        let state = serde_json::from_str::<serde_json::Value>(&state)
+            .expect("state file must contain JSON");
+        sim_assert_eq!(state["packages"]["foo"]["state"], "done");
+        sim_assert_eq!(state["packages"]["bar"]["state"], "publishing");
+    }
+
+    /// Orders packages into waves and explains every skipped package.
+    #[tokio::test]
+    async fn plan_reports_waves_and_skipped_packages() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/*"]
+resolver = "2"
+"#,
+        )
+        .expect("workspace manifest must be written");
+        write_member(temp.path(), "core", "");
+        write_member(temp.path(), "foo", "");
+        write_member(temp.path(), "baz", "");
+        write_member(temp.path(), "internal", "publish = false\n");
+        write_member(temp.path(), "docs", "");
+        write_member(
+            temp.path(),
+            "consumer",
+            r#"[dependencies]
+core = { path = "../core", version = "1.2.3" }
+foo = { path = "../foo", version = "1.2.3" }
+"#,
+        );
+
+        let registry = std::sync::Arc::new(super::InMemoryRegistry::new());
+        registry.insert("core", semver::Version::new(1, 2, 3));
+        let mut options = options(workspace_manifest_path);
+        options.registry_client = Some(registry);
+        options.concurrency_limit = Some(1);
+        options.exclude = Some(vec!["docs".to_string()]);
+
+        let plan = super::plan(options).await.expect("plan must succeed");
+
+        sim_assert_eq!(
+            plan.to_string(),
+            "wave 1:
+  baz@1.2.3
+wave 2:
+  foo@1.2.3
+wave 3:
+  consumer@1.2.3
+skipped:
+  core@1.2.3 (already published)
+  docs@1.2.3 (excluded)
+  internal@1.2.3 (publish = false)
+"
+        );
+    }
+
+    /// Selects packages by name and directory patterns, optionally with their dependencies.
+    #[tokio::test]
+    async fn plan_selects_packages_by_pattern_and_dependencies() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/*"]
+resolver = "2"
+"#,
+        )
+        .expect("workspace manifest must be written");
+        write_member(temp.path(), "core", "");
+        write_member(temp.path(), "tools", "");
+        write_member(
+            temp.path(),
+            "cli",
+            r#"[dependencies]
+core = { path = "../core", version = "1.2.3" }
+"#,
+        );
+        write_member(
+            temp.path(),
+            "app",
+            r#"[dependencies]
+cli = { path = "../cli", version = "1.2.3" }
+"#,
+        );
+
+        let plan = |include: &[&str], exclude: &[&str], include_dependencies: bool| {
+            let mut options = options(workspace_manifest_path.clone());
+            options.registry_client = Some(std::sync::Arc::new(super::InMemoryRegistry::new()));
+            options.include = Some(include.iter().map(ToString::to_string).collect());
+            options.exclude = Some(exclude.iter().map(ToString::to_string).collect());
+            options.include_dependencies = Some(include_dependencies);
+            super::plan(options)
+        };
+
+        sim_assert_eq!(
+            plan(&["c*"], &["crates/cl*"], false)
+                .await
+                .expect("plan must succeed")
+                .to_string(),
+            "wave 1:
+  core@1.2.3
+skipped:
+  app@1.2.3 (not included)
+  cli@1.2.3 (excluded)
+  tools@1.2.3 (not included)
+"
+        );
+        sim_assert_eq!(
+            plan(&["app"], &[], true)
+                .await
+                .expect("plan must succeed")
+                .to_string(),
+            "wave 1:
+  core@1.2.3
+wave 2:
+  cli@1.2.3
+wave 3:
+  app@1.2.3
+skipped:
+  tools@1.2.3 (not included)
+"
+        );
+        let error = plan(&["app"], &["core"], true)
+            .await
+            .expect_err("excluded dependencies must not be included");
+        assert!(
+            matches!(
+                &error,
+                super::Error::ExcludedDependency { package, dependency, .. }
+                    if package == "cli" && dependency == "core"
+            ),
+            "{error:?}"
+        );
+        sim_assert_eq!(
+            error.to_string(),
+            "cli: cannot publish because dependency core 1.2.3 is not selected and not published to crates.io"
+        );
+
+        // Deselected dependencies that are already published do not block their dependants.
+        let registry = std::sync::Arc::new(super::InMemoryRegistry::new());
+        registry.insert("core", semver::Version::new(1, 2, 3));
+        let mut options = options(workspace_manifest_path.clone());
+        options.registry_client = Some(registry);
+        options.include = Some(vec!["cli".to_string()]);
+        sim_assert_eq!(
+            super::plan(options)
+                .await
+                .expect("plan must succeed")
+                .to_string(),
+            "wave 1:
+  cli@1.2.3
+skipped:
+  app@1.2.3 (not included)
+  core@1.2.3 (not included)
+  tools@1.2.3 (not included)
+"
+        );
+    }
+
+    /// Applies workspace and package configuration unless options override it.
+    #[tokio::test]
+    async fn plan_applies_workspace_and_package_configuration() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/*"]
+resolver = "2"
+
+[workspace.metadata.publish-crates]
+exclude = ["tools"]
+"#,
+        )
+        .expect("workspace manifest must be written");
+        write_member(temp.path(), "core", "");
+        write_member(temp.path(), "tools", "");
+        write_member(
+            temp.path(),
+            "cli",
+            r#"[dependencies]
+core = { path = "../core", version = "1.2.3" }
+
+[package.metadata.publish-crates]
+publish-delay = "1s"
+"#,
+        );
+        write_member(
+            temp.path(),
+            "app",
+            "[package.metadata.publish-crates]
+skip = true
+",
+        );
+
+        let plan = |exclude: Option<Vec<String>>| {
+            let mut options = options(workspace_manifest_path.clone());
+            options.registry_client = Some(std::sync::Arc::new(super::InMemoryRegistry::new()));
+            options.exclude = exclude;
+            super::plan(options)
+        };
+        sim_assert_eq!(
+            plan(None).await.expect("plan must succeed").to_string(),
+            "wave 1:
+  core@1.2.3
+wave 2:
+  cli@1.2.3
+skipped:
+  app@1.2.3 (skip = true)
+  tools@1.2.3 (excluded)
+"
+        );
+        sim_assert_eq!(
+            plan(Some(Vec::new()))
+                .await
+                .expect("plan must succeed")
+                .to_string(),
+            "wave 1:
+  core@1.2.3
+  tools@1.2.3
+wave 2:
+  cli@1.2.3
+skipped:
+  app@1.2.3 (skip = true)
+"
+        );
+
+        write_member(
+            temp.path(),
+            "app",
+            r#"[package.metadata.publish-crates]
+skip = "yes"
+"#,
+        );
+        let error = plan(None)
+            .await
+            .expect_err("invalid configuration must fail");
+        assert!(
+            matches!(&error, super::Error::PackageConfig { package, .. } if package == "app"),
+            "{error:?}"
+        );
+        sim_assert_eq!(
+            error.to_string(),
+            r#"app: invalid [package.metadata.publish-crates]: invalid type: string "yes", expected a boolean"#
+        );
+        sim_assert_eq!(
+            error.suggestion().as_deref(),
+            Some("see the README for the supported settings")
+        );
+    }
+
+    /// Routes packages to the registries allowed by `package.publish` within one run.
+    #[tokio::test]
+    async fn packages_are_routed_to_allowed_registries() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/*"]
+resolver = "2"
+"#,
+        )
+        .expect("workspace manifest must be written");
+        std::fs::create_dir_all(temp.path().join(".cargo")).expect("config directory must exist");
+        std::fs::write(
+            temp.path().join(".cargo/config.toml"),
+            r#"[registries.private]
+index = "sparse+https://private.example.com/index/"
+"#,
+        )
+        .expect("cargo config must be written");
+        write_member(temp.path(), "core", "");
+        write_member(
+            temp.path(),
+            "internal",
+            r#"publish = ["private"]
+
+[dependencies]
+core = { path = "../core", version = "1.2.3" }
+"#,
+        );
+
+        let load = |extra_args: &[&str]| {
+            let mut options = options(workspace_manifest_path.clone());
+            options.registry_client = Some(std::sync::Arc::new(super::InMemoryRegistry::new()));
+            options.extra_args = extra_args.iter().map(ToString::to_string).collect();
+            async move {
+                super::load_packages(&options, None)
+                    .await
+                    .map(|(_, packages)| packages)
+            }
+        };
+        let packages = load(&[]).await.expect("packages must load");
+        let registries = packages
+            .values()
+            .map(|package| (package.inner.name.to_string(), package.registry.to_string()))
+            .collect::<std::collections::BTreeMap<_, _>>();
+        sim_assert_eq!(
+            registries,
+            [("core", "crates.io"), ("internal", "private")]
+                .map(|(name, registry)| (name.to_string(), registry.to_string()))
+                .into_iter()
+                .collect()
+        );
+
+        let error = load(&["--registry", "crates-io"])
+            .await
+            .expect_err("registries outside the publish list must be rejected");
+        sim_assert_eq!(
+            error.to_string(),
+            r#"internal: registry crates-io is not allowed by `package.publish = ["private"]`"#
+        );
+
+        // A single token must not authenticate packages published to several registries.
+        let load_with_token = |include: &[&str]| {
+            // Excluded dependencies must already be published.
+            let registry = super::InMemoryRegistry::new();
+            registry.insert("core", semver::Version::new(1, 2, 3));
+            let mut options = options(workspace_manifest_path.clone());
+            options.registry_client = Some(std::sync::Arc::new(registry));
+            options.registry_token = Some("token".to_string());
+            options.include = Some(include.iter().map(ToString::to_string).collect());
+            async move {
+                super::load_packages(&options, None)
+                    .await
+                    .map(|(_, packages)| packages)
+            }
+        };
+        let error = load_with_token(&["core", "internal"])
+            .await
+            .expect_err("a single token for several registries must be rejected");
+        sim_assert_eq!(
+            error.to_string(),
+            "a single registry token is ambiguous for packages published to crates.io, private"
+        );
+        let packages = load_with_token(&["internal"])
+            .await
+            .expect("a token for a single registry must be accepted");
+        let tokens = packages
+            .values()
+            .map(|package| {
+                (
+                    package.inner.name.to_string(),
+                    package.registry_token.clone(),
+                )
+            })
+            .collect::<std::collections::BTreeMap<_, _>>();
+        sim_assert_eq!(
+            tokens,
+            [("core", None), ("internal", Some("token".to_string()))]
+                .map(|(name, token)| (name.to_string(), token))
+                .into_iter()
+                .collect()
+        );
+    }
+
+    /// Selects packages changed since a git revision, optionally with their dependants.
+    #[tokio::test]
+    async fn plan_selects_packages_changed_since_revision() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/*"]
+resolver = "2"
+"#,
+        )
+        .expect("workspace manifest must be written");
+        write_member(temp.path(), "core", "");
+        write_member(temp.path(), "tool", "");
+        write_member(
+            temp.path(),
+            "consumer",
+            r#"[dependencies]
+core = { path = "../core", version = "1.2.3" }
+"#,
+        );
+        commit_all(temp.path());
+        std::fs::write(
+            temp.path().join("crates/core/src/lib.rs"),
+            "pub fn changed() {}\n",
+        )
+        .expect("package source must be modified");
+
+        let plan_since = |git_ref: &str, include_dependants: bool| {
+            let mut options = options(workspace_manifest_path.clone());
+            options.registry_client = Some(std::sync::Arc::new(super::InMemoryRegistry::new()));
+            options.changed_since = Some(git_ref.to_string());
+            options.include_dependants = Some(include_dependants);
+            super::plan(options)
+        };
+        let plan = |include_dependants: bool| plan_since("HEAD", include_dependants);
+
+        sim_assert_eq!(
+            plan(false).await.expect("plan must succeed").to_string(),
+            "wave 1:
+  core@1.2.3
+skipped:
+  consumer@1.2.3 (unchanged)
+  tool@1.2.3 (unchanged)
+"
+        );
+        sim_assert_eq!(
+            plan(true).await.expect("plan must succeed").to_string(),
+            "wave 1:
+  core@1.2.3
+wave 2:
+  consumer@1.2.3
+skipped:
+  tool@1.2.3 (unchanged)
+"
+        );
 
-        for (message, expected) in cases {
-            let error = super::classify_publish_error(message);
-            sim_assert_eq!(error, expected);
-            sim_assert_eq!(error.code(), expected.code());
-        }
+        // Revisions that look like options are rejected rather than passed to git as options.
+        let output = temp.path().join("diff.txt");
+        plan_since(&format!("--output={}", output.display()), false)
+            .await
+            .expect_err("option-like revision must be rejected");
+        assert!(!output.exists());
     }
 
-    #[test]
-    fn rejects_zero_concurrency_limit() {
-        let mut options = options(PathBuf::from("Cargo.toml"));
-        options.concurrency_limit = Some(0);
+    /// Selects the packages that inherit a changed `workspace.package.version`.
+    #[tokio::test]
+    async fn plan_selects_packages_inheriting_changed_workspace_version() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        let workspace_manifest = r#"[workspace]
+members = ["crates/*"]
+resolver = "2"
 
-        let error = options
-            .validate()
-            .expect_err("zero concurrency must be rejected");
+[workspace.package]
+version = "1.2.3"
+"#;
+        std::fs::write(&workspace_manifest_path, workspace_manifest)
+            .expect("workspace manifest must be written");
+        write_member(temp.path(), "core", "");
+        write_member(temp.path(), "tool", "");
+        let core_manifest_path = temp.path().join("crates/core/Cargo.toml");
+        let core_manifest = std::fs::read_to_string(&core_manifest_path)
+            .expect("package manifest must be readable")
+            .replace("version = \"1.2.3\"", "version.workspace = true");
+        std::fs::write(&core_manifest_path, core_manifest)
+            .expect("package manifest must be written");
+        commit_all(temp.path());
+        std::fs::write(
+            &workspace_manifest_path,
+            workspace_manifest.replace("1.2.3", "1.3.0"),
+        )
+        .expect("workspace manifest must be modified");
 
+        let mut options = options(workspace_manifest_path);
+        options.registry_client = Some(std::sync::Arc::new(super::InMemoryRegistry::new()));
+        options.changed_since = Some("HEAD".to_string());
         sim_assert_eq!(
-            error.to_string(),
-            "concurrency limit must be greater than zero"
+            super::plan(options)
+                .await
+                .expect("plan must succeed")
+                .to_string(),
+            "wave 1:
+  core@1.3.0
+skipped:
+  tool@1.2.3 (unchanged)
+"
         );
     }
 
-    #[test]
-    fn package_selection_applies_exclusions_after_inclusions() {
+    /// Renders dependency kinds on edges and selection attributes on nodes.
+    #[tokio::test]
+    async fn graph_exports_nodes_and_edges() {
         let temp = tempfile::tempdir().expect("temporary workspace must be created");
         let workspace_manifest_path = temp.path().join("Cargo.toml");
         std::fs::write(
             &workspace_manifest_path,
             r#"[workspace]
-members = ["crates/foo", "crates/bar"]
+members = ["crates/*"]
 resolver = "2"
 "#,
         )
         .expect("workspace manifest must be written");
         write_member(temp.path(), "foo", "");
-        write_member(temp.path(), "bar", "");
+        write_member(temp.path(), "internal", "publish = false\n");
+        write_member(
+            temp.path(),
+            "consumer",
+            r#"[dependencies]
+foo = { path = "../foo", version = "1.2.3" }
 
-        let metadata = cargo_metadata::MetadataCommand::new()
-            .manifest_path(&workspace_manifest_path)
-            .exec()
-            .expect("workspace metadata must load");
-        let mut options = options(workspace_manifest_path);
-        options.include = Some(vec!["foo".to_string(), "bar".to_string()]);
-        options.exclude = Some(vec!["bar".to_string()]);
+[dev-dependencies]
+foo = { path = "../foo", version = "1.2.3" }
+"#,
+        );
 
-        let selected = package_map(&metadata, &options)
-            .values()
-            .map(|package| (package.inner.name.to_string(), package.should_publish))
-            .collect::<std::collections::HashMap<_, _>>();
+        let graph = |format| super::graph(options(workspace_manifest_path.clone()), format);
 
-        sim_assert_eq!(selected.get("foo"), Some(&true));
-        sim_assert_eq!(selected.get("bar"), Some(&false));
+        sim_assert_eq!(
+            graph(super::GraphFormat::Dot)
+                .await
+                .expect("graph must render"),
+            r#"digraph publish {
+  "consumer" [label="consumer\n1.2.3", version="1.2.3", publishable=true, should_publish=true];
+  "foo" [label="foo\n1.2.3", version="1.2.3", publishable=true, should_publish=true];
+  "internal" [label="internal\n1.2.3", version="1.2.3", publishable=false, should_publish=false, style=dashed];
+  "consumer" -> "foo" [label="normal, dev"];
+}
+"#
+        );
+        sim_assert_eq!(
+            graph(super::GraphFormat::Mermaid)
+                .await
+                .expect("graph must render"),
+            r#"graph TD
+  p0["consumer@1.2.3"]
+  p1["foo@1.2.3"]
+  p2["internal@1.2.3"]:::skipped
+  p0 -->|normal, dev| p1
+  classDef skipped stroke-dasharray: 5 5
+"#
+        );
+        let json = graph(super::GraphFormat::Json)
+            .await
+            .expect("graph must render");
+        let json =
+            serde_json::from_str::<serde_json::Value>(&json).expect("graph must be valid JSON");
+        sim_assert_eq!(
+            json["edges"],
+            serde_json::json!([{"from": "consumer", "to": "foo", "kinds": ["normal", "dev"]}])
+        );
+        sim_assert_eq!(json["nodes"][2]["should_publish"], false);
     }
 
     /// Versions normal and build dependencies, including renamed manifest keys.
@@ -1209,7 +3132,7 @@ build-support = { path = "../build-support" }
             .exec()
             .expect("workspace metadata must load");
         let mut options = options(workspace_manifest_path);
-        options.resolve_versions = true;
+        options.resolve_versions = Some(true);
         let packages = package_map(&metadata, &options);
 
         super::build_dag(&metadata, &packages, &options)
@@ -1283,7 +3206,7 @@ test-support.workspace = true
             .exec()
             .expect("workspace metadata must load");
         let mut options = options(workspace_manifest_path.clone());
-        options.resolve_versions = true;
+        options.resolve_versions = Some(true);
         let packages = package_map(&metadata, &options);
 
         let changed = super::update_workspace_dependencies(&metadata, &packages, &options)
@@ -1350,7 +3273,7 @@ test-util = { path = "../test-util" }
             .exec()
             .expect("workspace metadata must load");
         let mut options = options(workspace_manifest_path);
-        options.resolve_versions = true;
+        options.resolve_versions = Some(true);
         let packages = package_map(&metadata, &options);
 
         super::build_dag(&metadata, &packages, &options)
@@ -1398,7 +3321,7 @@ test-support = { path = "../test-support" }
             .exec()
             .expect("workspace metadata must load");
         let mut options = options(workspace_manifest_path);
-        options.resolve_versions = true;
+        options.resolve_versions = Some(true);
         let packages = package_map(&metadata, &options);
 
         super::build_dag(&metadata, &packages, &options)
@@ -1463,7 +3386,7 @@ test-support = { path = "../test-support", version = "*" }
             .exec()
             .expect("workspace metadata must load");
         let mut options = options(workspace_manifest_path);
-        options.resolve_versions = true;
+        options.resolve_versions = Some(true);
         let packages = package_map(&metadata, &options);
 
         let error = super::build_dag(&metadata, &packages, &options)
@@ -1476,6 +3399,299 @@ test-support = { path = "../test-support", version = "*" }
         );
     }
 
+    /// Reports the packages and dependency kinds of a cycle instead of never becoming ready.
+    #[tokio::test]
+    async fn dependency_cycle_is_reported() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/core", "crates/macros"]
+resolver = "2"
+"#,
+        )
+        .expect("workspace manifest must be written");
+        write_member(
+            temp.path(),
+            "core",
+            r#"[dependencies]
+macros = { path = "../macros", version = "1.2.3" }
+"#,
+        );
+        write_member(
+            temp.path(),
+            "macros",
+            r#"[dev-dependencies]
+core = { path = "../core", version = "1.2.3" }
+"#,
+        );
+
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(&workspace_manifest_path)
+            .exec()
+            .expect("workspace metadata must load");
+        let options = options(workspace_manifest_path);
+        let packages = package_map(&metadata, &options);
+
+        let error = super::build_dag(&metadata, &packages, &options)
+            .await
+            .expect_err("dependency cycle must prevent publication");
+        sim_assert_eq!(
+            error.suggestion().as_deref(),
+            Some(
+                "remove the version requirement from the dev-dependency of macros on core so Cargo strips it when publishing"
+            )
+        );
+
+        sim_assert_eq!(
+            error.to_string(),
+            "dependency cycle between local packages: core → macros → core\ncore -> macros (normal)\nmacros -> core (dev)"
+        );
+    }
+
+    /// Reports every local requirement that does not match the workspace version.
+    #[tokio::test]
+    async fn mismatched_local_requirements_are_reported() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/core", "crates/macros", "crates/cli", "crates/app"]
+resolver = "2"
+
+[workspace.dependencies]
+macros = { path = "crates/macros", version = "2" }
+"#,
+        )
+        .expect("workspace manifest must be written");
+        write_member(temp.path(), "core", "");
+        write_member(temp.path(), "macros", "");
+        write_member(
+            temp.path(),
+            "cli",
+            r#"[dependencies]
+core = { path = "../core", version = "0.9" }
+
+[dev-dependencies]
+macros = { path = "../macros", version = "1.2" }
+"#,
+        );
+        write_member(
+            temp.path(),
+            "app",
+            r"[dependencies]
+macros.workspace = true
+",
+        );
+
+        // Resolving dependencies would already fail on the first mismatch.
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(&workspace_manifest_path)
+            .no_deps()
+            .exec()
+            .expect("workspace metadata must load");
+        let options = options(workspace_manifest_path.clone());
+        let packages = package_map(&metadata, &options);
+
+        let error = super::build_dag(&metadata, &packages, &options)
+            .await
+            .expect_err("mismatched requirements must prevent publication");
+
+        let member = |name: &str| temp.path().join("crates").join(name).join("Cargo.toml");
+        sim_assert_eq!(
+            error.to_string(),
+            format!(
+                "local dependency requirements do not match the workspace versions:\n\
+                {}:6: app requires macros ^2 but the workspace has macros 1.2.3\n\
+                {}:7: cli requires core ^0.9 but the workspace has core 1.2.3",
+                workspace_manifest_path.display(),
+                member("cli").display(),
+            )
+        );
+    }
+
+    /// Bumps changed packages, their dependants and the requirements on them.
+    #[tokio::test]
+    async fn bump_rewrites_versions_of_changed_packages_and_dependants() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/core", "crates/macros", "crates/cli", "crates/tool"]
+resolver = "2"
+
+[workspace.package]
+version = "1.2.3"
+
+[workspace.dependencies]
+core = { path = "crates/core", version = "=1.2.3" }
+"#,
+        )
+        .expect("workspace manifest must be written");
+        let write_package = |name: &str, version: &str, dependencies: &str| {
+            let package_dir = temp.path().join("crates").join(name);
+            std::fs::create_dir_all(package_dir.join("src"))
+                .expect("package source directory must be created");
+            std::fs::write(
+                package_dir.join("Cargo.toml"),
+                format!(
+                    "[package]\nname = \"{name}\"\n{version}\nedition = \"2021\"\n\n{dependencies}"
+                ),
+            )
+            .expect("package manifest must be written");
+            std::fs::write(package_dir.join("src/lib.rs"), "")
+                .expect("package source must be written");
+        };
+        write_package("core", "version.workspace = true", "");
+        write_package("macros", "version = \"0.4.0\" # released separately", "");
+        write_package(
+            "cli",
+            "version.workspace = true",
+            "[dependencies]\ncore.workspace = true\nmacros = { path = \"../macros\", version = \"=0.4.0\" }\n",
+        );
+        write_package("tool", "version = \"2.0.0\"", "");
+
+        commit_all(temp.path());
+        std::fs::write(
+            temp.path().join("crates/macros/src/lib.rs"),
+            "pub fn changed() {}\n",
+        )
+        .expect("package source must be modified");
+
+        let mut options = options(workspace_manifest_path.clone());
+        options.changed_since = Some("HEAD".to_string());
+        let report = super::bump(options, &super::BumpLevel::Minor)
+            .await
+            .expect("packages must be bumped");
+        sim_assert_eq!(
+            report.to_string(),
+            "cli: 1.2.3 -> 1.3.0\ncore: 1.2.3 -> 1.3.0\nmacros: 0.4.0 -> 0.5.0\n"
+        );
+
+        let read =
+            |path: PathBuf| std::fs::read_to_string(path).expect("manifest must be readable");
+        let workspace_manifest = read(workspace_manifest_path);
+        assert!(workspace_manifest.contains("version = \"1.3.0\""));
+        assert!(
+            workspace_manifest.contains(r#"core = { path = "crates/core", version = "=1.3.0" }"#)
+        );
+        assert!(
+            read(temp.path().join("crates/macros/Cargo.toml"))
+                .contains("version = \"0.5.0\" # released separately")
+        );
+        assert!(
+            read(temp.path().join("crates/cli/Cargo.toml"))
+                .contains(r#"macros = { path = "../macros", version = "=0.5.0" }"#)
+        );
+        assert!(read(temp.path().join("crates/tool/Cargo.toml")).contains("version = \"2.0.0\""));
+    }
+
+    /// Keeps the operators of existing requirements when no requirement style is given.
+    #[tokio::test]
+    async fn bump_keeps_operators_of_existing_requirements() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/core", "crates/app", "crates/cli", "crates/tool"]
+resolver = "2"
+
+[workspace.dependencies]
+core = { path = "crates/core", version = "~0.3.1" }
+"#,
+        )
+        .expect("workspace manifest must be written");
+        let write_package = |name: &str, dependencies: &str| {
+            write_member(temp.path(), name, dependencies);
+            let manifest_path = temp.path().join("crates").join(name).join("Cargo.toml");
+            let manifest = std::fs::read_to_string(&manifest_path)
+                .expect("package manifest must be readable")
+                .replace("version = \"1.2.3\"", "version = \"0.3.1\"");
+            std::fs::write(manifest_path, manifest).expect("package manifest must be written");
+        };
+        write_package("core", "");
+        write_package(
+            "app",
+            "[dependencies]\ncore = { path = \"../core\", version = \"0.3\" }\n",
+        );
+        write_package("cli", "[dependencies]\ncore.workspace = true\n");
+        write_package(
+            "tool",
+            "[dependencies]\ncore = { path = \"../core\", version = \">=0.3\" }\n",
+        );
+
+        let mut options = options(workspace_manifest_path.clone());
+        options.include = Some(vec!["core".to_string()]);
+        super::bump(options, &super::BumpLevel::Minor)
+            .await
+            .expect("packages must be bumped");
+
+        let read =
+            |path: PathBuf| std::fs::read_to_string(path).expect("manifest must be readable");
+        assert!(
+            read(workspace_manifest_path)
+                .contains(r#"core = { path = "crates/core", version = "~0.4.0" }"#)
+        );
+        assert!(
+            read(temp.path().join("crates/app/Cargo.toml"))
+                .contains(r#"core = { path = "../core", version = "^0.4" }"#)
+        );
+        assert!(
+            read(temp.path().join("crates/tool/Cargo.toml"))
+                .contains(r#"core = { path = "../core", version = ">=0.3" }"#)
+        );
+    }
+
+    /// Restores the manifests written before a failed write, so a bump applies to all or none.
+    #[tokio::test]
+    async fn bump_restores_written_manifests_when_a_write_fails() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        let workspace_manifest = r#"[workspace]
+members = ["crates/core", "crates/app"]
+resolver = "2"
+
+[workspace.dependencies]
+core = { path = "crates/core", version = "~1.2.3" }
+"#;
+        std::fs::write(&workspace_manifest_path, workspace_manifest)
+            .expect("workspace manifest must be written");
+        write_member(temp.path(), "core", "");
+        write_member(
+            temp.path(),
+            "app",
+            "[dependencies]\ncore.workspace = true\n",
+        );
+        let core_manifest_path = temp.path().join("crates/core/Cargo.toml");
+        let core_manifest =
+            std::fs::read_to_string(&core_manifest_path).expect("manifest must be readable");
+        // The temporary file of the core manifest cannot be created where a directory exists.
+        std::fs::create_dir(
+            temp.path()
+                .join("crates/core/.Cargo.toml.publish-crates.tmp"),
+        )
+        .expect("blocking directory must be created");
+
+        let mut options = options(workspace_manifest_path.clone());
+        options.include = Some(vec!["core".to_string()]);
+        super::bump(options, &super::BumpLevel::Minor)
+            .await
+            .expect_err("blocked manifest write must fail the bump");
+
+        sim_assert_eq!(
+            std::fs::read_to_string(&workspace_manifest_path).expect("manifest must be readable"),
+            workspace_manifest
+        );
+        sim_assert_eq!(
+            std::fs::read_to_string(&core_manifest_path).expect("manifest must be readable"),
+            core_manifest
+        );
+    }
+
     /// Versions workspace-inherited development dependencies on publishable packages.
     #[tokio::test]
     async fn workspace_publishable_dev_dependency_receives_version() {
@@ -1506,7 +3722,7 @@ test-support.workspace = true
             .exec()
             .expect("workspace metadata must load");
         let mut options = options(workspace_manifest_path.clone());
-        options.resolve_versions = true;
+        options.resolve_versions = Some(true);
         let packages = package_map(&metadata, &options);
 
         let changed = super::update_workspace_dependencies(&metadata, &packages, &options)
@@ -1531,6 +3747,65 @@ test-support.workspace = true
         );
     }
 
+    /// Writes the configured requirement style to workspace and member manifests.
+    #[tokio::test]
+    async fn version_req_style_applies_to_workspace_and_member_manifests() {
+        let temp = tempfile::tempdir().expect("temporary workspace must be created");
+        let workspace_manifest_path = temp.path().join("Cargo.toml");
+        std::fs::write(
+            &workspace_manifest_path,
+            r#"[workspace]
+members = ["crates/foo", "crates/bar", "crates/consumer"]
+resolver = "2"
+
+[workspace.dependencies]
+foo = { path = "crates/foo" }
+"#,
+        )
+        .expect("workspace manifest must be written");
+        write_member(temp.path(), "foo", "");
+        write_member(temp.path(), "bar", "");
+        write_member(
+            temp.path(),
+            "consumer",
+            r#"[dependencies]
+foo.workspace = true
+bar = { path = "../bar" }
+"#,
+        );
+
+        let metadata = cargo_metadata::MetadataCommand::new()
+            .manifest_path(&workspace_manifest_path)
+            .exec()
+            .expect("workspace metadata must load");
+        let mut options = options(workspace_manifest_path.clone());
+        options.resolve_versions = Some(true);
+        options.version_req_style = Some(super::VersionReqStyle::Tilde);
+        let packages = package_map(&metadata, &options);
+
+        super::update_workspace_dependencies(&metadata, &packages, &options)
+            .await
+            .expect("workspace dependencies must resolve");
+        super::build_dag(&metadata, &packages, &options)
+            .await
+            .expect("dependency graph must resolve");
+
+        let workspace_manifest = std::fs::read_to_string(workspace_manifest_path)
+            .expect("workspace manifest must be readable");
+        assert!(
+            workspace_manifest.contains(r#"foo = { path = "crates/foo", version = "~1.2.3" }"#)
+        );
+        let consumer_manifest = std::fs::read_to_string(
+            temp.path()
+                .join("crates")
+                .join("consumer")
+                .join("Cargo.toml"),
+        )
+        .expect("consumer manifest must be readable");
+        assert!(consumer_manifest.contains(r#"bar = { path = "../bar", version = "~1.2.3" }"#));
+        assert!(consumer_manifest.contains("foo.workspace = true"));
+    }
+
     /// Rejects private local packages used as production dependencies.
     #[tokio::test]
     async fn private_normal_dependency_is_rejected() {
@@ -1619,7 +3894,7 @@ bar.workspace = true
         let packages = package_map(&metadata, &options(workspace_manifest_path.clone()));
 
         let mut options = options(workspace_manifest_path.clone());
-        options.resolve_versions = true;
+        options.resolve_versions = Some(true);
 
         // Exercise the helper in dry-run mode before allowing it to persist changes.
         let original_manifest = std::fs::read_to_string(&workspace_manifest_path).unwrap();
@@ -1677,7 +3952,7 @@ bar.workspace = true
 
         // Exercise the helper again with resolve_versions disabled; this should
         // also be a no-op without errors.
-        options.resolve_versions = false;
+        options.resolve_versions = Some(false);
         let changed = super::update_workspace_dependencies(&metadata, &packages, &options)
             .await
             .unwrap();
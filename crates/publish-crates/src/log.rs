@@ -0,0 +1,61 @@
+//! Human-readable log lines.
+//!
+//! Lines use the workflow command syntax of [`action_core`] and go to stdout, unless
+//! [`MessageFormat::Json`] reserves stdout for events, in which case they go to stderr.
+
+use crate::MessageFormat;
+use action_core as action;
+
+/// Destination of log lines, derived from [`crate::Options::message_format`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Log {
+    /// Whether log lines go to stderr instead of stdout.
+    to_stderr: bool,
+}
+
+impl Log {
+    /// Sends log lines to stderr for [`MessageFormat::Json`] and to stdout otherwise.
+    pub(crate) fn new(format: MessageFormat) -> Self {
+        Self {
+            to_stderr: format == MessageFormat::Json,
+        }
+    }
+
+    /// Writes a plain log line.
+    pub(crate) fn write(self, line: std::fmt::Arguments) {
+        if self.to_stderr {
+            eprintln!("{line}");
+        } else {
+            println!("{line}");
+        }
+    }
+
+    /// Writes a log line as a workflow command of `level`.
+    pub(crate) fn issue(self, level: action::LogLevel, message: String) {
+        let command = action::CommandBuilder::new(level.to_string(), message).build();
+        self.write(format_args!("{command}"));
+    }
+}
+
+/// Like [`action_core::info`], but writes to the destination of a [`Log`].
+macro_rules! info {
+    ($log:expr, $($arg:tt)*) => {
+        $crate::log::Log::write($log, format_args!($($arg)*))
+    };
+}
+
+/// Like [`action_core::warning`], but writes to the destination of a [`Log`].
+macro_rules! warning {
+    ($log:expr, $($arg:tt)*) => {
+        $crate::log::Log::issue($log, action_core::LogLevel::Warning, format!($($arg)*))
+    };
+}
+
+/// Like [`action_core::debug`], but writes to the destination of a [`Log`].
+macro_rules! debug {
+    ($log:expr, $($arg:tt)*) => {
+        $crate::log::Log::issue($log, action_core::LogLevel::Debug, format!($($arg)*))
+    };
+}
+
+pub(crate) use {debug, info, warning};
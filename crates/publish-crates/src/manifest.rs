@@ -0,0 +1,235 @@
+//! Crash-safe manifest writes and snapshots of workspace files modified while publishing.
+
+use crate::Error;
+use crate::log;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Original contents of files that publishing may modify.
+#[derive(Debug, Default)]
+pub(crate) struct ManifestSnapshots {
+    /// File contents by path, or [`None`] for files that did not exist.
+    files: Mutex<BTreeMap<PathBuf, Option<Vec<u8>>>>,
+}
+
+impl ManifestSnapshots {
+    /// Records the contents of `path` unless it was already recorded.
+    pub(crate) fn record(&self, path: &Path) -> crate::Result<()> {
+        let mut files = self.files.lock();
+        if files.contains_key(path) {
+            return Ok(());
+        }
+        let contents = match std::fs::read(path) {
+            Ok(contents) => Some(contents),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => {
+                return Err(Error::ManifestEdit {
+                    path: path.to_path_buf(),
+                    message: format!("failed to snapshot: {err}"),
+                });
+            }
+        };
+        files.insert(path.to_path_buf(), contents);
+        Ok(())
+    }
+
+    /// Writes back every recorded file that changed, and removes files that did not exist.
+    ///
+    /// Files are written back atomically like [`write_manifest`]. All files are attempted even if
+    /// some fail, so a single failure does not leave the other manifests modified.
+    pub(crate) async fn restore(&self, log: log::Log) -> crate::Result<()> {
+        let files = self.files.lock().clone();
+        let mut failed = Vec::new();
+        for (path, original) in &files {
+            let current = tokio::fs::read(path).await.ok();
+            if current == *original {
+                continue;
+            }
+            log::info!(log, "restoring {}", path.display());
+            let restored = match original {
+                Some(contents) => {
+                    let permissions = tokio::fs::metadata(path)
+                        .await
+                        .ok()
+                        .map(|metadata| metadata.permissions());
+                    replace_file(path, contents, permissions).await
+                }
+                None => tokio::fs::remove_file(path).await,
+            };
+            if let Err(err) = restored {
+                log::warning!(log, "failed to restore {}: {err}", path.display());
+                failed.push(Error::ManifestEdit {
+                    path: path.clone(),
+                    message: format!("failed to restore: {err}"),
+                });
+            }
+        }
+        // Every failure was logged, so report the first.
+        if let Some(err) = failed.into_iter().next() {
+            return Err(err);
+        }
+        Ok(())
+    }
+}
+
+/// Converts line endings of `contents` to CRLF when `original` uses CRLF.
+///
+/// Edits of `toml_edit` insert LF line endings, which would otherwise mix with the CRLF endings of
+/// manifests checked out on Windows.
+fn match_line_endings(original: &str, contents: &str) -> String {
+    if original.contains("\r\n") {
+        contents.replace("\r\n", "\n").replace('\n', "\r\n")
+    } else {
+        contents.to_string()
+    }
+}
+
+/// Replaces the manifest at `path` with `contents`, which were derived from `original`.
+///
+/// The contents are written to a temporary sibling file that is renamed into place, so a crash
+/// never leaves a truncated manifest. The file permissions and line endings of the original are
+/// preserved. Writing fails if the file no longer contains `original`, rather than overwriting
+/// changes made since it was read.
+pub(crate) async fn write_manifest(
+    path: &Path,
+    original: &str,
+    contents: &str,
+) -> crate::Result<()> {
+    let current = tokio::fs::read_to_string(path).await?;
+    if current != original {
+        return Err(Error::ManifestEdit {
+            path: path.to_path_buf(),
+            message: "changed on disk since it was read, refusing to overwrite it".to_string(),
+        });
+    }
+    let permissions = tokio::fs::metadata(path).await?.permissions();
+    let contents = match_line_endings(original, contents);
+    replace_file(path, contents.as_bytes(), Some(permissions))
+        .await
+        .map_err(|err| Error::ManifestEdit {
+            path: path.to_path_buf(),
+            message: format!("failed to write: {err}"),
+        })
+}
+
+/// Replaces the file at `path` with `contents` through a temporary sibling file that is renamed
+/// into place, so a crash never leaves a truncated file.
+async fn replace_file(
+    path: &Path,
+    contents: &[u8],
+    permissions: Option<std::fs::Permissions>,
+) -> std::io::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a file"))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(".publish-crates.tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let written = async {
+        use tokio::io::AsyncWriteExt;
+        let mut file = tokio::fs::File::create(&temp_path).await?;
+        file.write_all(contents).await?;
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions).await?;
+        }
+        file.sync_all().await?;
+        drop(file);
+        tokio::fs::rename(&temp_path, path).await
+    }
+    .await;
+    if written.is_err() {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+    }
+    written
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ManifestSnapshots;
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    #[tokio::test]
+    async fn restores_modified_and_removes_created_files() {
+        let temp = tempfile::tempdir().expect("temporary directory must be created");
+        let manifest = temp.path().join("Cargo.toml");
+        let lockfile = temp.path().join("Cargo.lock");
+        std::fs::write(&manifest, "[package]\n").expect("manifest must be written");
+
+        let snapshots = ManifestSnapshots::default();
+        snapshots
+            .record(&manifest)
+            .expect("manifest must be recorded");
+        snapshots
+            .record(&lockfile)
+            .expect("missing lockfile must be recorded");
+
+        std::fs::write(&manifest, "[package]\nversion = \"1.0.0\"\n")
+            .expect("manifest must be modified");
+        // Later snapshots of the same file keep the original contents.
+        snapshots
+            .record(&manifest)
+            .expect("manifest must be recorded");
+        std::fs::write(&lockfile, "version = 4\n").expect("lockfile must be created");
+
+        snapshots
+            .restore(crate::log::Log::default())
+            .await
+            .expect("files must be restored");
+        sim_assert_eq!(
+            std::fs::read_to_string(&manifest).expect("manifest must be readable"),
+            "[package]\n"
+        );
+        assert!(!lockfile.exists());
+        sim_assert_eq!(
+            std::fs::read_dir(temp.path())
+                .expect("directory must be readable")
+                .count(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn writes_manifests_atomically_and_detects_changes() {
+        let temp = tempfile::tempdir().expect("temporary directory must be created");
+        let manifest = temp.path().join("Cargo.toml");
+        let original = "[package]\r\nname = \"foo\"\r\n";
+        std::fs::write(&manifest, original).expect("manifest must be written");
+
+        super::write_manifest(
+            &manifest,
+            original,
+            "[package]\r\nname = \"foo\"\r\nversion = \"1.0.0\"\n",
+        )
+        .await
+        .expect("manifest must be written");
+        let written = std::fs::read_to_string(&manifest).expect("manifest must be readable");
+        sim_assert_eq!(
+            written.as_str(),
+            "[package]\r\nname = \"foo\"\r\nversion = \"1.0.0\"\r\n"
+        );
+        sim_assert_eq!(
+            std::fs::read_dir(temp.path())
+                .expect("directory must be readable")
+                .count(),
+            1
+        );
+
+        let error = super::write_manifest(&manifest, original, "[package]\n")
+            .await
+            .expect_err("stale contents must not be written");
+        sim_assert_eq!(
+            error.to_string(),
+            format!(
+                "{}: changed on disk since it was read, refusing to overwrite it",
+                manifest.display()
+            )
+        );
+        sim_assert_eq!(
+            std::fs::read_to_string(&manifest).expect("manifest must be readable"),
+            written
+        );
+    }
+}
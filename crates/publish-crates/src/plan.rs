@@ -0,0 +1,181 @@
+//! Read-only publish plans.
+
+use crate::{Options, Package, StateStore};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Reason a workspace package is not published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// The manifest sets `publish = false`.
+    NotPublishable,
+    /// The package is not listed in [`Options::include`].
+    NotIncluded,
+    /// The package is listed in [`Options::exclude`].
+    Excluded,
+    /// The package sets `skip = true` in `[package.metadata.publish-crates]`.
+    Configured,
+    /// The package has not changed since [`Options::changed_since`].
+    Unchanged,
+    /// The exact package version already exists on its registry.
+    AlreadyPublished,
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let reason = match self {
+            Self::NotPublishable => "publish = false",
+            Self::NotIncluded => "not included",
+            Self::Excluded => "excluded",
+            Self::Configured => "skip = true",
+            Self::Unchanged => "unchanged",
+            Self::AlreadyPublished => "already published",
+        };
+        write!(f, "{reason}")
+    }
+}
+
+/// A package that a publish run would upload.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct PlannedPackage {
+    /// Package name.
+    pub name: String,
+    /// Package version.
+    pub version: semver::Version,
+}
+
+/// A package that a publish run would not upload.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct SkippedPackage {
+    /// Package name.
+    pub name: String,
+    /// Package version.
+    pub version: semver::Version,
+    /// Why the package is skipped.
+    pub reason: SkipReason,
+}
+
+/// Publish order computed without modifying manifests or uploading packages.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Plan {
+    /// Packages in publish order.
+    ///
+    /// Packages of one wave have no dependencies on each other or on later waves and run
+    /// concurrently. Each wave holds at most [`Options::concurrency_limit`] packages.
+    pub waves: Vec<Vec<PlannedPackage>>,
+    /// Workspace packages that are not published, sorted by name.
+    pub skipped: Vec<SkippedPackage>,
+}
+
+impl std::fmt::Display for Plan {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.waves.is_empty() {
+            writeln!(f, "nothing to publish")?;
+        }
+        for (index, wave) in self.waves.iter().enumerate() {
+            writeln!(f, "wave {}:", index + 1)?;
+            for package in wave {
+                writeln!(f, "  {}@{}", package.name, package.version)?;
+            }
+        }
+        if !self.skipped.is_empty() {
+            writeln!(f, "skipped:")?;
+            for package in &self.skipped {
+                writeln!(
+                    f,
+                    "  {}@{} ({})",
+                    package.name, package.version, package.reason
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Groups packages that still need publishing into waves of at most `limit` packages.
+fn waves(
+    packages: &HashMap<PathBuf, Arc<Package>>,
+    limit: usize,
+) -> crate::Result<Vec<Vec<PlannedPackage>>> {
+    let mut remaining: Vec<&Arc<Package>> = packages
+        .values()
+        .filter(|package| package.should_publish() && !package.published())
+        .collect();
+    let mut scheduled: HashSet<&str> = HashSet::new();
+    let mut waves = Vec::new();
+
+    while !remaining.is_empty() {
+        let (mut level, rest): (Vec<_>, Vec<_>) = remaining.into_iter().partition(|package| {
+            package.deps.read().values().all(|dep| {
+                dep.package.published() || scheduled.contains(dep.package.inner.name.as_str())
+            })
+        });
+        if level.is_empty() {
+            let mut names = rest
+                .iter()
+                .map(|package| package.inner.name.to_string())
+                .collect::<Vec<_>>();
+            names.sort();
+            return Err(crate::Error::Unschedulable { packages: names });
+        }
+        level.sort_by(|a, b| a.inner.name.cmp(&b.inner.name));
+        scheduled.extend(level.iter().map(|package| package.inner.name.as_str()));
+        waves.extend(level.chunks(limit.max(1)).map(|chunk| {
+            chunk
+                .iter()
+                .map(|package| PlannedPackage {
+                    name: package.inner.name.to_string(),
+                    version: package.inner.version.clone(),
+                })
+                .collect()
+        }));
+        remaining = rest;
+    }
+    Ok(waves)
+}
+
+/// Builds the plan of a loaded package map.
+pub(crate) fn build(
+    packages: &HashMap<PathBuf, Arc<Package>>,
+    limit: usize,
+) -> crate::Result<Plan> {
+    let waves = waves(packages, limit)?;
+
+    let mut skipped = packages
+        .values()
+        .filter_map(|package| {
+            let reason = package
+                .skip_reason
+                .or_else(|| package.published().then_some(SkipReason::AlreadyPublished))?;
+            Some(SkippedPackage {
+                name: package.inner.name.to_string(),
+                version: package.inner.version.clone(),
+                reason,
+            })
+        })
+        .collect::<Vec<_>>();
+    skipped.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(Plan { waves, skipped })
+}
+
+/// Computes the publish order of selected workspace packages without side effects.
+///
+/// Like a [`crate::publish`] dry-run, local dependency versions are resolved in memory only. The
+/// registry is queried to report packages whose version is already published.
+///
+/// # Errors
+///
+/// Returns an error when the options, configuration or Cargo metadata are invalid, dependency
+/// versions cannot be resolved, a selected package depends on an excluded package, or local
+/// dependencies form a cycle.
+pub async fn plan(options: Options) -> crate::Result<Plan> {
+    let mut options = options.with_workspace_config()?;
+    // Planning never modifies manifests.
+    options.dry_run = true;
+    let (_, packages) = crate::load_packages(&options, None).await?;
+    crate::skip_published_packages(&packages, &StateStore::in_memory()).await;
+    build(&packages, options.concurrency_limit.unwrap_or(4))
+}
@@ -0,0 +1,659 @@
+//! Target registry resolution and registry clients.
+//!
+//! Cargo selects the registry for `cargo publish` from `--registry`/`--index`, from a single-entry
+//! `package.publish` list, or from `registry.default` in its configuration. The same rules are
+//! applied here so availability checks query the registry that actually received the package, and
+//! every package is published with an explicit `--registry`.
+
+mod crates_io;
+mod git;
+mod index;
+mod memory;
+mod sparse;
+
+pub use crates_io::CratesIoClient;
+pub use git::GitIndexClient;
+pub use memory::InMemoryRegistry;
+pub use sparse::SparseIndexClient;
+
+use crate::Error;
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// User agent sent with every registry request.
+///
+/// crates.io rejects requests without a user agent that identifies the client.
+const USER_AGENT: &str = "publish_crates (https://github.com/romnn/publish-crates)";
+
+/// Name Cargo uses for the crates.io registry.
+const CRATES_IO: &str = "crates-io";
+
+/// A registry request that the registry asked to retry later, such as `429 Too Many Requests` with
+/// a `Retry-After` header.
+///
+/// Availability checks wait until [`Self::retry_after`] instead of failing.
+#[derive(Debug, thiserror::Error)]
+#[error("{url} responded with {status}, retry after {}", humantime::format_rfc3339_seconds(*.retry_after))]
+pub struct RateLimited {
+    /// Requested URL.
+    pub url: String,
+    /// Response status.
+    pub status: http::StatusCode,
+    /// Time the registry allows the next request at.
+    pub retry_after: std::time::SystemTime,
+}
+
+impl RateLimited {
+    /// Returns the rate limit of a `429 Too Many Requests` or `503 Service Unavailable` response
+    /// with a valid `Retry-After` header.
+    pub(crate) fn from_response(response: &reqwest::Response) -> Option<Self> {
+        let status = response.status();
+        if !matches!(
+            status,
+            http::StatusCode::TOO_MANY_REQUESTS | http::StatusCode::SERVICE_UNAVAILABLE
+        ) {
+            return None;
+        }
+        let header = response.headers().get(http::header::RETRY_AFTER)?;
+        let retry_after =
+            crate::retry::retry_after_header(header.to_str().ok()?, std::time::SystemTime::now())?;
+        Some(Self {
+            url: response.url().to_string(),
+            status,
+            retry_after,
+        })
+    }
+}
+
+/// A package version listed by a registry.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PublishedVersion {
+    /// The published version.
+    pub version: semver::Version,
+    /// Whether the version has been yanked.
+    pub yanked: bool,
+}
+
+/// Queries a package registry for published package versions.
+///
+/// Implementations must be cheap to call repeatedly: availability checks poll
+/// [`Self::is_downloadable`] until a freshly uploaded version is served.
+pub trait RegistryClient: std::fmt::Debug + Send + Sync {
+    /// Lists every version of a package known to the registry, including yanked versions.
+    ///
+    /// A package the registry does not know yields an empty list.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the registry cannot be queried.
+    fn versions<'a>(&'a self, name: &'a str)
+    -> BoxFuture<'a, crate::Result<Vec<PublishedVersion>>>;
+
+    /// Checks whether the registry lists a package version, yanked or not.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the registry cannot be queried.
+    fn version_exists<'a>(
+        &'a self,
+        name: &'a str,
+        version: &'a semver::Version,
+    ) -> BoxFuture<'a, crate::Result<bool>> {
+        Box::pin(async move {
+            let versions = self.versions(name).await?;
+            Ok(versions
+                .iter()
+                .any(|published| &published.version == version))
+        })
+    }
+
+    /// Checks whether the `.crate` file of a package version can be downloaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the registry cannot be queried.
+    fn is_downloadable<'a>(
+        &'a self,
+        name: &'a str,
+        version: &'a semver::Version,
+    ) -> BoxFuture<'a, crate::Result<bool>>;
+}
+
+/// Registry that receives a published package.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Registry {
+    /// The default crates.io registry.
+    CratesIo,
+    /// An alternative registry served through the sparse index protocol.
+    Sparse {
+        /// Registry name from Cargo configuration, or [`None`] for `--index` URLs.
+        name: Option<String>,
+        /// Index URL without the `sparse+` prefix, always ending in `/`.
+        index: String,
+    },
+    /// An alternative registry served through a git index.
+    Git {
+        /// Registry name from Cargo configuration, or [`None`] for `--index` URLs.
+        name: Option<String>,
+        /// Git URL of the index repository.
+        index: String,
+    },
+}
+
+impl std::fmt::Display for Registry {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::CratesIo => write!(f, "crates.io"),
+            Self::Sparse {
+                name: Some(name), ..
+            }
+            | Self::Git {
+                name: Some(name), ..
+            } => write!(f, "{name}"),
+            Self::Sparse { name: None, index } | Self::Git { name: None, index } => {
+                write!(f, "{index}")
+            }
+        }
+    }
+}
+
+impl Registry {
+    /// Resolves the registry `cargo publish` uses for a package.
+    ///
+    /// Explicit `--registry` or `--index` arguments take precedence over the `configured` registry
+    /// of the package, which takes precedence over a single-entry `package.publish` list and
+    /// `registry.default`. Like Cargo, a registry missing from the `package.publish` list is
+    /// rejected, and so is an `--index` for a package with such a list.
+    pub(crate) fn resolve(
+        package: &cargo_metadata::Package,
+        extra_args: &[String],
+        configured: Option<&str>,
+        config: &CargoConfig,
+    ) -> crate::Result<Self> {
+        let allowed = package.publish.as_deref().unwrap_or_default();
+        if let Some(index) = argument_value(extra_args, "--index") {
+            if !allowed.is_empty() {
+                return Err(Error::RegistryNotAllowed {
+                    package: package.name.to_string(),
+                    registry: index.to_string(),
+                    allowed: allowed.to_vec(),
+                });
+            }
+            return Ok(Self::from_index(None, index));
+        }
+        let single_publish_registry = match allowed {
+            [registry] => Some(registry.as_str()),
+            _ => None,
+        };
+        let name = argument_value(extra_args, "--registry")
+            .or(configured)
+            .or(single_publish_registry)
+            .map(str::to_owned)
+            .or_else(|| config.default_registry())
+            .unwrap_or_else(|| CRATES_IO.to_string());
+
+        if !allowed.is_empty() && !allowed.contains(&name) {
+            return Err(Error::RegistryNotAllowed {
+                package: package.name.to_string(),
+                registry: name,
+                allowed: allowed.to_vec(),
+            });
+        }
+        if name == CRATES_IO {
+            return Ok(Self::CratesIo);
+        }
+        let index = config.index(&name).ok_or_else(|| {
+            Error::Registry(format!(
+                "{}: registry {name} is not configured in `[registries]` of any Cargo config",
+                package.name
+            ))
+        })?;
+        Ok(Self::from_index(Some(name), &index))
+    }
+
+    fn from_index(name: Option<String>, index: &str) -> Self {
+        let Some(index) = index.strip_prefix("sparse+") else {
+            return Self::Git {
+                name,
+                index: index.to_string(),
+            };
+        };
+        let index = if index.ends_with('/') {
+            index.to_string()
+        } else {
+            format!("{index}/")
+        };
+        Self::Sparse { name, index }
+    }
+
+    fn name(&self) -> Option<&str> {
+        match self {
+            Self::CratesIo => None,
+            Self::Sparse { name, .. } | Self::Git { name, .. } => name.as_deref(),
+        }
+    }
+
+    /// Returns the `--registry` argument that selects this registry, unless it is only known by
+    /// its index URL.
+    pub(crate) fn cargo_name(&self) -> Option<&str> {
+        match self {
+            Self::CratesIo => Some(CRATES_IO),
+            Self::Sparse { name, .. } | Self::Git { name, .. } => name.as_deref(),
+        }
+    }
+
+    /// Returns the environment variable Cargo reads the token for this registry from.
+    pub(crate) fn token_env_var(&self) -> String {
+        match self.name() {
+            Some(name) => format!("CARGO_REGISTRIES_{}_TOKEN", env_key(name)),
+            None => "CARGO_REGISTRY_TOKEN".to_string(),
+        }
+    }
+
+    /// Creates the client that queries this registry.
+    ///
+    /// Git indexes are fetched into a directory below `cache_dir`.
+    pub(crate) fn client(
+        &self,
+        token: Option<&str>,
+        cache_dir: &Path,
+    ) -> crate::Result<Arc<dyn RegistryClient>> {
+        Ok(match self {
+            Self::CratesIo => Arc::new(CratesIoClient::new()?),
+            Self::Sparse { index, .. } => Arc::new(SparseIndexClient::new(
+                index.clone(),
+                token.map(str::to_owned),
+            )?),
+            Self::Git { index, .. } => Arc::new(GitIndexClient::new(
+                index.clone(),
+                token.map(str::to_owned),
+                cache_dir,
+            )?),
+        })
+    }
+}
+
+/// Returns the value of a `--flag value` or `--flag=value` argument.
+pub(crate) fn argument_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next().map(String::as_str);
+        }
+        if let Some(value) = arg
+            .strip_prefix(flag)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Converts a registry name to the form Cargo uses in environment variable names.
+fn env_key(name: &str) -> String {
+    name.to_uppercase().replace('-', "_")
+}
+
+/// Registry settings merged from Cargo configuration files.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct CargoConfig {
+    default_registry: Option<String>,
+    registries: HashMap<String, String>,
+    /// Tokens by registry name, with `crates-io` for `[registry] token`.
+    tokens: HashMap<String, String>,
+}
+
+impl CargoConfig {
+    /// Loads Cargo configuration that applies to a directory.
+    ///
+    /// Like Cargo, files closer to `dir` take precedence over files in parent directories, which
+    /// take precedence over `$CARGO_HOME/config.toml`. Tokens in `$CARGO_HOME/credentials.toml`
+    /// take precedence over tokens in configuration files.
+    pub(crate) fn discover(dir: &Path) -> crate::Result<Self> {
+        let mut config_dirs: Vec<PathBuf> = dir.ancestors().map(|dir| dir.join(".cargo")).collect();
+        let cargo_home = cargo_home();
+        if let Some(cargo_home) = &cargo_home
+            && !config_dirs.contains(cargo_home)
+        {
+            config_dirs.push(cargo_home.clone());
+        }
+
+        let mut config = Self::default();
+        // Apply the farthest configuration first so closer files override it.
+        let config_files = config_dirs.iter().rev().flat_map(|config_dir| {
+            ["config", "config.toml"].map(|file_name| config_dir.join(file_name))
+        });
+        let credential_files = cargo_home.iter().flat_map(|cargo_home| {
+            ["credentials", "credentials.toml"].map(|file_name| cargo_home.join(file_name))
+        });
+        for path in config_files.chain(credential_files) {
+            if !path.is_file() {
+                continue;
+            }
+            let contents = std::fs::read_to_string(&path)?;
+            config
+                .merge(&contents)
+                .map_err(|error| Error::CargoConfig {
+                    path: path.clone(),
+                    error,
+                })?;
+        }
+        Ok(config)
+    }
+
+    fn merge(&mut self, contents: &str) -> Result<(), toml_edit::TomlError> {
+        let document = contents.parse::<toml_edit::DocumentMut>()?;
+        let registry = document.get("registry");
+        if let Some(default) = registry
+            .and_then(|registry| registry.get("default"))
+            .and_then(toml_edit::Item::as_str)
+        {
+            self.default_registry = Some(default.to_string());
+        }
+        if let Some(token) = registry
+            .and_then(|registry| registry.get("token"))
+            .and_then(toml_edit::Item::as_str)
+        {
+            self.tokens.insert(CRATES_IO.to_string(), token.to_string());
+        }
+        if let Some(registries) = document
+            .get("registries")
+            .and_then(toml_edit::Item::as_table_like)
+        {
+            for (name, registry) in registries.iter() {
+                if let Some(index) = registry.get("index").and_then(toml_edit::Item::as_str) {
+                    self.registries.insert(name.to_string(), index.to_string());
+                }
+                if let Some(token) = registry.get("token").and_then(toml_edit::Item::as_str) {
+                    self.tokens.insert(name.to_string(), token.to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn default_registry(&self) -> Option<String> {
+        std::env::var("CARGO_REGISTRY_DEFAULT")
+            .ok()
+            .or_else(|| self.default_registry.clone())
+    }
+
+    /// Returns the token Cargo uses for `registry`, from its environment variable or the
+    /// configuration.
+    ///
+    /// Registries only known by their index URL have no token, so no other registry's token is
+    /// ever sent to them.
+    pub(crate) fn token(&self, registry: &Registry) -> Option<String> {
+        let name = registry.cargo_name()?;
+        std::env::var(registry.token_env_var())
+            .ok()
+            .or_else(|| self.tokens.get(name).cloned())
+    }
+
+    fn index(&self, name: &str) -> Option<String> {
+        std::env::var(format!("CARGO_REGISTRIES_{}_INDEX", env_key(name)))
+            .ok()
+            .or_else(|| self.registries.get(name).cloned())
+    }
+}
+
+fn cargo_home() -> Option<PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::home_dir().map(|home| home.join(".cargo")))
+}
+
+fn http_client() -> crate::Result<reqwest::Client> {
+    Ok(reqwest::Client::builder().user_agent(USER_AGENT).build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CargoConfig, Registry};
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    /// Answers one HTTP request per response, in order, on a local port.
+    ///
+    /// Returns the server URL and the received requests, lowercased.
+    pub(super) async fn serve(
+        responses: &'static [&'static str],
+    ) -> (
+        String,
+        tokio::task::JoinHandle<std::io::Result<Vec<String>>>,
+    ) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("test server must bind");
+        let url = format!(
+            "http://{}",
+            listener
+                .local_addr()
+                .expect("test server must have an address")
+        );
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().await?;
+                let mut request = vec![0; 4096];
+                let len = stream.read(&mut request).await?;
+                requests.push(String::from_utf8_lossy(&request[..len]).to_lowercase());
+                stream.write_all(response.as_bytes()).await?;
+            }
+            Ok(requests)
+        });
+        (url, server)
+    }
+
+    fn package(publish: Option<Vec<&str>>) -> cargo_metadata::Package {
+        let publish = publish.map_or_else(
+            || "null".to_string(),
+            |registries| format!("{registries:?}"),
+        );
+        serde_json::from_str(&format!(
+            r#"{{
+                "name": "foo",
+                "version": "1.2.3",
+                "id": "foo 1.2.3",
+                "dependencies": [],
+                "targets": [],
+                "features": {{}},
+                "manifest_path": "/workspace/foo/Cargo.toml",
+                "publish": {publish}
+            }}"#
+        ))
+        .expect("package metadata must deserialize")
+    }
+
+    fn config(contents: &str) -> CargoConfig {
+        let mut config = CargoConfig::default();
+        config.merge(contents).expect("config must parse");
+        config
+    }
+
+    #[test]
+    fn resolves_registry_from_arguments_publish_list_and_default() {
+        let config = config(
+            r#"
+[registry]
+default = "fallback"
+
+[registries.private]
+index = "sparse+https://private.example.com/index"
+
+[registries.fallback]
+index = "sparse+https://fallback.example.com/"
+"#,
+        );
+        let private = Registry::Sparse {
+            name: Some("private".to_string()),
+            index: "https://private.example.com/index/".to_string(),
+        };
+
+        let args = ["--registry".to_string(), "private".to_string()];
+        sim_assert_eq!(
+            Registry::resolve(&package(None), &args, None, &config).unwrap(),
+            private
+        );
+        let args = ["--registry=private".to_string()];
+        sim_assert_eq!(
+            Registry::resolve(&package(None), &args, None, &config).unwrap(),
+            private
+        );
+        sim_assert_eq!(
+            Registry::resolve(&package(Some(vec!["private"])), &[], None, &config).unwrap(),
+            private
+        );
+        sim_assert_eq!(
+            Registry::resolve(
+                &package(Some(vec!["private", "fallback"])),
+                &[],
+                None,
+                &config
+            )
+            .unwrap()
+            .to_string(),
+            "fallback"
+        );
+        let args = ["--registry".to_string(), "crates-io".to_string()];
+        sim_assert_eq!(
+            Registry::resolve(&package(None), &args, None, &config).unwrap(),
+            Registry::CratesIo
+        );
+        sim_assert_eq!(
+            Registry::resolve(&package(None), &[], Some("private"), &config).unwrap(),
+            private
+        );
+        sim_assert_eq!(
+            Registry::resolve(&package(Some(vec!["crates-io"])), &[], None, &config).unwrap(),
+            Registry::CratesIo
+        );
+
+        let error = Registry::resolve(&package(Some(vec!["private"])), &args, None, &config)
+            .expect_err("registries outside the publish list must be rejected");
+        sim_assert_eq!(
+            error.to_string(),
+            r#"foo: registry crates-io is not allowed by `package.publish = ["private"]`"#
+        );
+        let error = Registry::resolve(
+            &package(Some(vec!["crates-io", "private"])),
+            &[],
+            None,
+            &config,
+        )
+        .expect_err("the default registry must be in the publish list");
+        sim_assert_eq!(
+            error.to_string(),
+            r#"foo: registry fallback is not allowed by `package.publish = ["crates-io", "private"]`"#
+        );
+    }
+
+    #[test]
+    fn resolves_git_index_and_rejects_unknown_registries() {
+        let config = config(
+            r#"
+[registries.git]
+index = "https://git.example.com/index.git"
+"#,
+        );
+
+        let args = ["--registry".to_string(), "unknown".to_string()];
+        let error = Registry::resolve(&package(None), &args, None, &config)
+            .expect_err("unknown registries must be rejected");
+        sim_assert_eq!(
+            error.to_string(),
+            "foo: registry unknown is not configured in `[registries]` of any Cargo config"
+        );
+
+        let args = ["--registry".to_string(), "git".to_string()];
+        sim_assert_eq!(
+            Registry::resolve(&package(None), &args, None, &config).unwrap(),
+            Registry::Git {
+                name: Some("git".to_string()),
+                index: "https://git.example.com/index.git".to_string(),
+            }
+        );
+
+        let args = [
+            "--index".to_string(),
+            "https://git.example.com/index.git".to_string(),
+        ];
+        sim_assert_eq!(
+            Registry::resolve(&package(None), &args, None, &config).unwrap(),
+            Registry::Git {
+                name: None,
+                index: "https://git.example.com/index.git".to_string(),
+            }
+        );
+        let error = Registry::resolve(&package(Some(vec!["private"])), &args, None, &config)
+            .expect_err("an index must be rejected when package.publish lists registries");
+        sim_assert_eq!(
+            error.to_string(),
+            "foo: registry https://git.example.com/index.git is not allowed by `package.publish = [\"private\"]`"
+        );
+    }
+
+    #[test]
+    fn discovers_closest_cargo_config() {
+        let temp = tempfile::tempdir().expect("temporary directory must be created");
+        let nested = temp.path().join("nested");
+        std::fs::create_dir_all(nested.join(".cargo")).expect("config directory must be created");
+        std::fs::create_dir_all(temp.path().join(".cargo"))
+            .expect("config directory must be created");
+        std::fs::write(
+            temp.path().join(".cargo/config.toml"),
+            "[registries.publish-crates-test]\nindex = \"sparse+https://outer.example.com/\"\n",
+        )
+        .expect("config must be written");
+        std::fs::write(
+            nested.join(".cargo/config.toml"),
+            "[registries.publish-crates-test]\nindex = \"sparse+https://inner.example.com/\"\n",
+        )
+        .expect("config must be written");
+
+        let config = CargoConfig::discover(&nested).expect("config must load");
+        sim_assert_eq!(
+            config.index("publish-crates-test").as_deref(),
+            Some("sparse+https://inner.example.com/")
+        );
+    }
+
+    #[test]
+    fn token_environment_variable_matches_registry() {
+        let registry = Registry::Sparse {
+            name: Some("my-registry".to_string()),
+            index: "https://example.com/".to_string(),
+        };
+        sim_assert_eq!(
+            registry.token_env_var(),
+            "CARGO_REGISTRIES_MY_REGISTRY_TOKEN"
+        );
+        sim_assert_eq!(Registry::CratesIo.token_env_var(), "CARGO_REGISTRY_TOKEN");
+    }
+
+    #[test]
+    fn reads_tokens_of_named_registries_only() {
+        let config = config(
+            r#"
+[registry]
+token = "crates-io-token"
+
+[registries.publish-crates-token-test]
+index = "sparse+https://private.example.com/"
+token = "private-token"
+"#,
+        );
+        let private = Registry::from_index(
+            Some("publish-crates-token-test".to_string()),
+            "sparse+https://private.example.com/",
+        );
+        sim_assert_eq!(config.token(&private).as_deref(), Some("private-token"));
+        // Registries only known by their index URL never receive another registry's token.
+        let unnamed = Registry::from_index(None, "sparse+https://private.example.com/");
+        sim_assert_eq!(config.token(&unnamed), None);
+    }
+}
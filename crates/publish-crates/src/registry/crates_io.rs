@@ -0,0 +1,86 @@
+//! Registry client for crates.io.
+
+use super::{PublishedVersion, RateLimited, RegistryClient, USER_AGENT, http_client};
+use futures::future::BoxFuture;
+
+/// Queries crates.io through its web API.
+#[derive(Clone)]
+pub struct CratesIoClient {
+    api: crates_io_api::AsyncClient,
+    http: reqwest::Client,
+}
+
+impl std::fmt::Debug for CratesIoClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("CratesIoClient").finish_non_exhaustive()
+    }
+}
+
+impl CratesIoClient {
+    /// Creates a client that sends at most one API request per second.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the HTTP client cannot be initialized.
+    pub fn new() -> crate::Result<Self> {
+        Ok(Self {
+            api: crates_io_api::AsyncClient::new(USER_AGENT, std::time::Duration::from_secs(1))
+                .map_err(|err| crate::Error::Registry(format!("invalid user agent: {err}")))?,
+            http: http_client()?,
+        })
+    }
+
+    async fn crate_versions(&self, name: &str) -> crate::Result<Vec<crates_io_api::Version>> {
+        match self.api.get_crate(name).await {
+            Ok(info) => Ok(info.versions),
+            Err(crates_io_api::Error::NotFound(_)) => Ok(Vec::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+impl RegistryClient for CratesIoClient {
+    fn versions<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, crate::Result<Vec<PublishedVersion>>> {
+        Box::pin(async move {
+            let versions = self.crate_versions(name).await?;
+            Ok(versions
+                .iter()
+                .filter_map(|v| {
+                    Some(PublishedVersion {
+                        version: semver::Version::parse(&v.num).ok()?,
+                        yanked: v.yanked,
+                    })
+                })
+                .collect())
+        })
+    }
+
+    fn is_downloadable<'a>(
+        &'a self,
+        name: &'a str,
+        version: &'a semver::Version,
+    ) -> BoxFuture<'a, crate::Result<bool>> {
+        Box::pin(async move {
+            let versions = self.crate_versions(name).await?;
+            let Some(published) = versions
+                .iter()
+                .find(|v| semver::Version::parse(&v.num).is_ok_and(|num| &num == version))
+            else {
+                return Ok(false);
+            };
+
+            let dl_response = self
+                .http
+                .head(format!("https://crates.io{}", published.dl_path))
+                .send()
+                .await?;
+            if let Some(limit) = RateLimited::from_response(&dl_response) {
+                return Err(limit.into());
+            }
+            Ok(dl_response.status() == reqwest::StatusCode::OK)
+        })
+    }
+}
@@ -0,0 +1,172 @@
+//! Registry client for git registry indexes.
+
+use super::index::{self, IndexConfig, IndexEntry};
+use super::{PublishedVersion, RegistryClient, http_client};
+use futures::future::BoxFuture;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How long a fetched index commit answers lookups before it is fetched again.
+///
+/// Shorter than the poll interval, so that lookups of one poll share a fetch while every poll
+/// still observes the latest commit.
+const FETCH_TTL: Duration = Duration::from_secs(3);
+
+/// Queries a registry through its git index using the `git` command-line tool.
+///
+/// Lookups fetch the latest index commit into a bare repository, at most once per
+/// [`FETCH_TTL`], so credentials for the index are handled by git's own configuration.
+pub struct GitIndexClient {
+    index: String,
+    repository: PathBuf,
+    token: Option<String>,
+    http: reqwest::Client,
+    /// Time of the last successful fetch, locked while fetching and reading `FETCH_HEAD`.
+    fetched: tokio::sync::Mutex<Option<Instant>>,
+    config: tokio::sync::OnceCell<IndexConfig>,
+}
+
+impl std::fmt::Debug for GitIndexClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // Never print the token.
+        f.debug_struct("GitIndexClient")
+            .field("index", &self.index)
+            .field("repository", &self.repository)
+            .field("authenticated", &self.token.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl GitIndexClient {
+    /// Creates a client for a git index URL that keeps its bare repository below `cache_dir`.
+    ///
+    /// The token is sent as the `Authorization` header when probing downloads of registries that
+    /// declare `auth-required`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the HTTP client cannot be initialized.
+    pub fn new(
+        index: impl Into<String>,
+        token: Option<String>,
+        cache_dir: &Path,
+    ) -> crate::Result<Self> {
+        use std::hash::{Hash, Hasher};
+
+        let index = index.into();
+        let mut hasher = std::hash::DefaultHasher::new();
+        index.hash(&mut hasher);
+        Ok(Self {
+            repository: cache_dir.join(format!("{:016x}", hasher.finish())),
+            index,
+            token,
+            http: http_client()?,
+            fetched: tokio::sync::Mutex::new(None),
+            config: tokio::sync::OnceCell::new(),
+        })
+    }
+
+    async fn git(&self, args: &[&str]) -> crate::Result<std::process::Output> {
+        let mut cmd = async_process::Command::new("git");
+        cmd.arg("-C").arg(&self.repository).args(args);
+        Ok(cmd.output().await?)
+    }
+
+    /// Reads a file from the latest index commit, fetching it unless fetched within
+    /// [`FETCH_TTL`].
+    ///
+    /// Returns [`None`] when the file does not exist in the index.
+    async fn read_file(&self, path: &str) -> crate::Result<Option<String>> {
+        let mut fetched = self.fetched.lock().await;
+        if fetched.is_none_or(|at| at.elapsed() >= FETCH_TTL) {
+            self.fetch().await?;
+            *fetched = Some(Instant::now());
+        }
+
+        let output = self.git(&["show", &format!("FETCH_HEAD:{path}")]).await?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+    }
+
+    /// Fetches the latest index commit into the bare repository, creating it first if needed.
+    async fn fetch(&self) -> crate::Result<()> {
+        if !self.repository.join("HEAD").is_file() {
+            tokio::fs::create_dir_all(&self.repository).await?;
+            let output = self.git(&["init", "--bare", "--quiet"]).await?;
+            if !output.status.success() {
+                return Err(crate::Error::Registry(format!(
+                    "failed to initialize git index cache {}: {}",
+                    self.repository.display(),
+                    String::from_utf8_lossy(&output.stderr)
+                )));
+            }
+        }
+
+        let output = self
+            .git(&["fetch", "--quiet", "--depth", "1", &self.index, "HEAD"])
+            .await?;
+        if !output.status.success() {
+            return Err(crate::Error::Registry(format!(
+                "failed to fetch git index {}: {}",
+                self.index,
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+        Ok(())
+    }
+
+    async fn config(&self) -> crate::Result<&IndexConfig> {
+        self.config
+            .get_or_try_init(|| async {
+                let config = self.read_file("config.json").await?.ok_or_else(|| {
+                    crate::Error::Registry(format!("git index {} has no config.json", self.index))
+                })?;
+                Ok(serde_json::from_str(&config)?)
+            })
+            .await
+    }
+
+    async fn entries(&self, name: &str) -> crate::Result<Vec<IndexEntry>> {
+        let contents = self.read_file(&index::index_path(name)).await?;
+        Ok(contents
+            .as_deref()
+            .map(index::parse_entries)
+            .unwrap_or_default())
+    }
+}
+
+impl RegistryClient for GitIndexClient {
+    fn versions<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, crate::Result<Vec<PublishedVersion>>> {
+        Box::pin(async move {
+            let entries = self.entries(name).await?;
+            Ok(entries
+                .iter()
+                .filter_map(IndexEntry::published_version)
+                .collect())
+        })
+    }
+
+    fn is_downloadable<'a>(
+        &'a self,
+        name: &'a str,
+        version: &'a semver::Version,
+    ) -> BoxFuture<'a, crate::Result<bool>> {
+        Box::pin(async move {
+            let entries = self.entries(name).await?;
+            let Some(entry) = index::find_entry(&entries, version).filter(|entry| !entry.yanked)
+            else {
+                return Ok(false);
+            };
+
+            let config = self.config().await?;
+            let dl_url = index::download_url(&config.dl, name, &entry.vers, &entry.cksum);
+            let token = self.token.as_deref().filter(|_| config.auth_required);
+            index::is_downloadable(&self.http, dl_url, token).await
+        })
+    }
+}
@@ -0,0 +1,208 @@
+//! Index file layout shared by sparse and git registry indexes.
+
+use super::{PublishedVersion, RateLimited};
+
+/// Index configuration served at `config.json`.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub(super) struct IndexConfig {
+    pub(super) dl: String,
+    #[serde(rename = "auth-required", default)]
+    pub(super) auth_required: bool,
+}
+
+/// One line of an index file.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub(super) struct IndexEntry {
+    pub(super) vers: String,
+    pub(super) cksum: String,
+    #[serde(default)]
+    pub(super) yanked: bool,
+}
+
+impl IndexEntry {
+    pub(super) fn published_version(&self) -> Option<PublishedVersion> {
+        Some(PublishedVersion {
+            version: semver::Version::parse(&self.vers).ok()?,
+            yanked: self.yanked,
+        })
+    }
+}
+
+/// Parses the newline-delimited JSON entries of an index file.
+///
+/// Lines that do not parse are skipped, as Cargo does for entries written by newer registries.
+pub(super) fn parse_entries(contents: &str) -> Vec<IndexEntry> {
+    contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Finds the index entry of an exact package version.
+pub(super) fn find_entry<'a>(
+    entries: &'a [IndexEntry],
+    version: &semver::Version,
+) -> Option<&'a IndexEntry> {
+    entries
+        .iter()
+        .find(|entry| semver::Version::parse(&entry.vers).is_ok_and(|vers| &vers == version))
+}
+
+/// Returns the directory prefix of a crate in the index, preserving the case of `name`.
+fn index_prefix(name: &str) -> String {
+    match name.len() {
+        1 => "1".to_string(),
+        2 => "2".to_string(),
+        3 => format!("3/{}", name.get(..1).unwrap_or_default()),
+        _ => format!(
+            "{}/{}",
+            name.get(..2).unwrap_or_default(),
+            name.get(2..4).unwrap_or_default()
+        ),
+    }
+}
+
+/// Returns the path of a crate's index file relative to the index root.
+pub(super) fn index_path(name: &str) -> String {
+    let name = name.to_lowercase();
+    format!("{}/{name}", index_prefix(&name))
+}
+
+/// Expands the `dl` template from `config.json` into a download URL.
+///
+/// Templates without markers receive `/{crate}/{version}/download`, as specified by Cargo.
+pub(super) fn download_url(template: &str, name: &str, version: &str, checksum: &str) -> String {
+    const MARKERS: [&str; 5] = [
+        "{crate}",
+        "{version}",
+        "{prefix}",
+        "{lowerprefix}",
+        "{sha256-checksum}",
+    ];
+    if !MARKERS.iter().any(|marker| template.contains(marker)) {
+        return format!(
+            "{}/{name}/{version}/download",
+            template.trim_end_matches('/')
+        );
+    }
+    let prefix = index_prefix(name);
+    template
+        .replace("{crate}", name)
+        .replace("{version}", version)
+        .replace("{lowerprefix}", &prefix.to_lowercase())
+        .replace("{prefix}", &prefix)
+        .replace("{sha256-checksum}", checksum)
+}
+
+/// Checks whether a package archive can be downloaded from `url`.
+///
+/// The token is sent as the `Authorization` header when given, and a rate-limited response is
+/// returned as [`crate::Error::RateLimited`].
+pub(super) async fn is_downloadable(
+    http: &reqwest::Client,
+    url: String,
+    token: Option<&str>,
+) -> crate::Result<bool> {
+    let mut request = http.head(url);
+    if let Some(token) = token {
+        request = request.header(reqwest::header::AUTHORIZATION, token);
+    }
+    let response = request.send().await?;
+    if let Some(limit) = RateLimited::from_response(&response) {
+        return Err(limit.into());
+    }
+    Ok(response.status().is_success())
+}
+
+#[cfg(test)]
+mod tests {
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    #[test]
+    fn computes_index_paths_and_download_urls() {
+        sim_assert_eq!(super::index_path("a"), "1/a");
+        sim_assert_eq!(super::index_path("ab"), "2/ab");
+        sim_assert_eq!(super::index_path("abc"), "3/a/abc");
+        sim_assert_eq!(super::index_path("Serde"), "se/rd/serde");
+
+        sim_assert_eq!(
+            super::download_url(
+                "https://dl.example.com/api/v1/crates",
+                "Serde",
+                "1.0.0",
+                "ab"
+            ),
+            "https://dl.example.com/api/v1/crates/Serde/1.0.0/download"
+        );
+        sim_assert_eq!(
+            super::download_url(
+                "https://dl.example.com/{prefix}/{lowerprefix}/{crate}-{version}.crate?sum={sha256-checksum}",
+                "Serde",
+                "1.0.0",
+                "ab"
+            ),
+            "https://dl.example.com/Se/rd/se/rd/Serde-1.0.0.crate?sum=ab"
+        );
+    }
+
+    #[test]
+    fn parses_index_entries_and_skips_invalid_lines() {
+        let entries = super::parse_entries(
+            r#"{"name":"foo","vers":"1.0.0","cksum":"a","yanked":false}
+not json
+{"name":"foo","vers":"1.1.0","cksum":"b","yanked":true}
+"#,
+        );
+        let versions = entries
+            .iter()
+            .filter_map(super::IndexEntry::published_version)
+            .map(|published| (published.version.to_string(), published.yanked))
+            .collect::<Vec<_>>();
+        sim_assert_eq!(
+            versions,
+            vec![("1.0.0".to_string(), false), ("1.1.0".to_string(), true)]
+        );
+
+        let entry = super::find_entry(&entries, &semver::Version::new(1, 1, 0))
+            .expect("published version must be found");
+        sim_assert_eq!(entry.cksum.as_str(), "b");
+    }
+
+    #[tokio::test]
+    async fn download_probe_reports_rate_limits() {
+        let (server_url, server) = crate::registry::tests::serve(&[
+            "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 120\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+        ])
+        .await;
+        let url = format!("{server_url}/foo/1.0.0/download");
+        let http = super::super::http_client().unwrap();
+        let result = super::is_downloadable(&http, url.clone(), Some("secret")).await;
+
+        let request = server.await.unwrap().unwrap().concat();
+        assert!(request.starts_with("head /foo/1.0.0/download"), "{request}");
+        assert!(request.contains("authorization: secret"), "{request}");
+        match result {
+            Err(crate::Error::RateLimited(limit)) => {
+                sim_assert_eq!(limit.url, url);
+                sim_assert_eq!(limit.status, http::StatusCode::TOO_MANY_REQUESTS);
+                assert!(limit.retry_after > std::time::SystemTime::now());
+            }
+            other => panic!("expected a rate limit, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn download_probe_reports_available_archives() {
+        let (server_url, server) = crate::registry::tests::serve(&[
+            "HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+        ])
+        .await;
+        let url = format!("{server_url}/foo/1.0.0/download");
+        let http = super::super::http_client().unwrap();
+        let downloadable = super::is_downloadable(&http, url, None).await.unwrap();
+
+        let request = server.await.unwrap().unwrap().concat();
+        assert!(!request.contains("authorization"), "{request}");
+        assert!(downloadable);
+    }
+}
@@ -0,0 +1,66 @@
+//! In-memory registry for tests and offline tooling.
+
+use super::{PublishedVersion, RegistryClient};
+use futures::future::BoxFuture;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+/// A registry that keeps published versions in memory.
+///
+/// Versions added with [`Self::insert`] are immediately listed and downloadable.
+#[derive(Debug, Default)]
+pub struct InMemoryRegistry {
+    packages: Mutex<HashMap<String, Vec<PublishedVersion>>>,
+}
+
+impl InMemoryRegistry {
+    /// Creates an empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a published package version.
+    pub fn insert(&self, name: impl Into<String>, version: semver::Version) {
+        self.packages
+            .lock()
+            .entry(name.into())
+            .or_default()
+            .push(PublishedVersion {
+                version,
+                yanked: false,
+            });
+    }
+
+    /// Marks a published package version as yanked.
+    pub fn yank(&self, name: &str, version: &semver::Version) {
+        if let Some(versions) = self.packages.lock().get_mut(name) {
+            for published in versions.iter_mut().filter(|v| &v.version == version) {
+                published.yanked = true;
+            }
+        }
+    }
+}
+
+impl RegistryClient for InMemoryRegistry {
+    fn versions<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, crate::Result<Vec<PublishedVersion>>> {
+        let versions = self.packages.lock().get(name).cloned().unwrap_or_default();
+        Box::pin(async move { Ok(versions) })
+    }
+
+    fn is_downloadable<'a>(
+        &'a self,
+        name: &'a str,
+        version: &'a semver::Version,
+    ) -> BoxFuture<'a, crate::Result<bool>> {
+        let downloadable = self.packages.lock().get(name).is_some_and(|versions| {
+            versions
+                .iter()
+                .any(|published| &published.version == version && !published.yanked)
+        });
+        Box::pin(async move { Ok(downloadable) })
+    }
+}
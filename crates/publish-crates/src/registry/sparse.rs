@@ -0,0 +1,195 @@
+//! Registry client for the sparse index protocol.
+
+use super::index::{self, IndexConfig, IndexEntry};
+use super::{PublishedVersion, RateLimited, RegistryClient, http_client};
+use futures::future::BoxFuture;
+use reqwest::StatusCode;
+
+/// Queries a registry through its sparse index, as used by Kellnr, Cloudsmith or Artifactory.
+pub struct SparseIndexClient {
+    index: String,
+    token: Option<String>,
+    http: reqwest::Client,
+    config: tokio::sync::OnceCell<IndexConfig>,
+}
+
+impl std::fmt::Debug for SparseIndexClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // Never print the token.
+        f.debug_struct("SparseIndexClient")
+            .field("index", &self.index)
+            .field("authenticated", &self.token.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl SparseIndexClient {
+    /// Creates a client for a sparse index URL, with or without the `sparse+` prefix.
+    ///
+    /// Like Cargo, `config.json` is first fetched anonymously and only fetched again with the
+    /// token as the `Authorization` header when the registry answers `401 Unauthorized`. For
+    /// registries that declare `auth-required`, the token is sent with every other request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the HTTP client cannot be initialized.
+    pub fn new(index: impl Into<String>, token: Option<String>) -> crate::Result<Self> {
+        let index = index.into();
+        let index = index.strip_prefix("sparse+").unwrap_or(&index);
+        let index = if index.ends_with('/') {
+            index.to_string()
+        } else {
+            format!("{index}/")
+        };
+        Ok(Self {
+            index,
+            token,
+            http: http_client()?,
+            config: tokio::sync::OnceCell::new(),
+        })
+    }
+
+    fn get(&self, url: String, auth: bool) -> reqwest::RequestBuilder {
+        // Bypass intermediate caches so polling observes freshly published versions.
+        let request = self
+            .http
+            .get(url)
+            .header(reqwest::header::CACHE_CONTROL, "no-cache");
+        match &self.token {
+            Some(token) if auth => request.header(reqwest::header::AUTHORIZATION, token),
+            _ => request,
+        }
+    }
+
+    async fn config(&self) -> crate::Result<&IndexConfig> {
+        self.config
+            .get_or_try_init(|| async {
+                let config_url = format!("{}config.json", self.index);
+                let mut response = self.get(config_url.clone(), false).send().await?;
+                if response.status() == StatusCode::UNAUTHORIZED && self.token.is_some() {
+                    response = self.get(config_url.clone(), true).send().await?;
+                }
+                if let Some(limit) = RateLimited::from_response(&response) {
+                    return Err(limit.into());
+                }
+                if !response.status().is_success() {
+                    return Err(crate::Error::Registry(format!(
+                        "failed to fetch sparse index configuration {config_url}: {}",
+                        response.status()
+                    )));
+                }
+                Ok(serde_json::from_slice(&response.bytes().await?)?)
+            })
+            .await
+    }
+
+    async fn entries(&self, name: &str) -> crate::Result<Vec<IndexEntry>> {
+        let config = self.config().await?;
+        let response = self
+            .get(
+                format!("{}{}", self.index, index::index_path(name)),
+                config.auth_required,
+            )
+            .send()
+            .await?;
+        if let Some(limit) = RateLimited::from_response(&response) {
+            return Err(limit.into());
+        }
+        // Cargo treats these statuses as a crate that does not exist yet.
+        if matches!(
+            response.status(),
+            StatusCode::NOT_FOUND | StatusCode::GONE | StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS
+        ) {
+            return Ok(Vec::new());
+        }
+        if !response.status().is_success() {
+            return Err(crate::Error::Registry(format!(
+                "failed to fetch sparse index entry for {name}: {}",
+                response.status()
+            )));
+        }
+        Ok(index::parse_entries(&response.text().await?))
+    }
+}
+
+impl RegistryClient for SparseIndexClient {
+    fn versions<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, crate::Result<Vec<PublishedVersion>>> {
+        Box::pin(async move {
+            let entries = self.entries(name).await?;
+            Ok(entries
+                .iter()
+                .filter_map(IndexEntry::published_version)
+                .collect())
+        })
+    }
+
+    fn is_downloadable<'a>(
+        &'a self,
+        name: &'a str,
+        version: &'a semver::Version,
+    ) -> BoxFuture<'a, crate::Result<bool>> {
+        Box::pin(async move {
+            let entries = self.entries(name).await?;
+            let Some(entry) = index::find_entry(&entries, version).filter(|entry| !entry.yanked)
+            else {
+                return Ok(false);
+            };
+
+            let config = self.config().await?;
+            let dl_url = index::download_url(&config.dl, name, &entry.vers, &entry.cksum);
+            let token = self.token.as_deref().filter(|_| config.auth_required);
+            index::is_downloadable(&self.http, dl_url, token).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SparseIndexClient;
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    const NOT_FOUND: &str =
+        "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n";
+
+    #[tokio::test]
+    async fn fetches_config_anonymously_unless_unauthorized() {
+        let (url, server) = crate::registry::tests::serve(&[
+            "HTTP/1.1 200 OK\r\ncontent-length: 27\r\nconnection: close\r\n\r\n{\"dl\":\"https://dl.example\"}",
+            NOT_FOUND,
+        ])
+        .await;
+        let client = SparseIndexClient::new(url, Some("secret".to_string())).unwrap();
+        sim_assert_eq!(client.entries("foo").await.unwrap().len(), 0);
+        let requests = server.await.unwrap().unwrap();
+        assert!(requests[0].starts_with("get /config.json"), "{requests:?}");
+        assert!(
+            requests
+                .iter()
+                .all(|request| !request.contains("authorization")),
+            "{requests:?}"
+        );
+
+        let (url, server) = crate::registry::tests::serve(&[
+            "HTTP/1.1 401 Unauthorized\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\ncontent-length: 48\r\nconnection: close\r\n\r\n{\"dl\":\"https://dl.example\",\"auth-required\":true}",
+            NOT_FOUND,
+        ])
+        .await;
+        let client = SparseIndexClient::new(url, Some("secret".to_string())).unwrap();
+        sim_assert_eq!(client.entries("foo").await.unwrap().len(), 0);
+        let requests = server.await.unwrap().unwrap();
+        assert!(!requests[0].contains("authorization"), "{requests:?}");
+        assert!(
+            requests[1].contains("authorization: secret"),
+            "{requests:?}"
+        );
+        assert!(requests[2].starts_with("get /3/f/foo"), "{requests:?}");
+        assert!(
+            requests[2].contains("authorization: secret"),
+            "{requests:?}"
+        );
+    }
+}
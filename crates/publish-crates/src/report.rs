@@ -0,0 +1,214 @@
+//! Per-package results of a publish run.
+
+use crate::{Package, PublishError, SkipReason};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Final outcome of a package in a publish run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The package was published by this run, or by the interrupted run it resumed.
+    ///
+    /// During a dry-run, the package passed Cargo's publishing checks.
+    Published,
+    /// The package is not selected for publishing, or its version already exists on the registry.
+    Skipped(SkipReason),
+    /// Publishing the package failed.
+    Failed,
+    /// The run ended before the package was published, usually because another package failed.
+    Unfinished,
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Published => write!(f, "published"),
+            Self::Skipped(reason) => write!(f, "skipped ({reason})"),
+            Self::Failed => write!(f, "failed"),
+            Self::Unfinished => write!(f, "unfinished"),
+        }
+    }
+}
+
+/// Progress recorded for a package while publishing.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct PackageStats {
+    pub(crate) outcome: Option<Outcome>,
+    pub(crate) attempts: usize,
+    pub(crate) upload: Option<Duration>,
+    pub(crate) availability_wait: Option<Duration>,
+    pub(crate) delay: Option<Duration>,
+    pub(crate) error: Option<PublishError>,
+}
+
+/// Result of publishing one package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageReport {
+    /// Package name.
+    pub name: String,
+    /// Package version.
+    pub version: semver::Version,
+    /// Final outcome.
+    pub outcome: Outcome,
+    /// Number of `cargo publish` invocations.
+    pub attempts: usize,
+    /// Time spent in `cargo publish`, including retries and their backoff.
+    pub upload: Option<Duration>,
+    /// Time spent waiting for the registry to serve the uploaded version.
+    pub availability_wait: Option<Duration>,
+    /// Delay applied after the package became available.
+    pub delay: Option<Duration>,
+    /// Classification of the last failed `cargo publish` attempt.
+    ///
+    /// Packages that eventually succeeded keep the classification of the failure they recovered
+    /// from.
+    pub error: Option<PublishError>,
+}
+
+/// Results of a publish run, returned by [`crate::publish`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PublishReport {
+    /// Every workspace package, sorted by name.
+    pub packages: Vec<PackageReport>,
+}
+
+impl PublishReport {
+    /// Collects the recorded progress of every package.
+    pub(crate) fn new(packages: &HashMap<PathBuf, Arc<Package>>) -> Self {
+        let mut packages = packages
+            .values()
+            .map(|package| {
+                let stats = package.stats.lock().clone();
+                let outcome = match (package.skip_reason, stats.outcome) {
+                    (Some(reason), _) => Outcome::Skipped(reason),
+                    (None, Some(outcome)) => outcome,
+                    (None, None) => Outcome::Unfinished,
+                };
+                PackageReport {
+                    name: package.inner.name.to_string(),
+                    version: package.inner.version.clone(),
+                    outcome,
+                    attempts: stats.attempts,
+                    upload: stats.upload,
+                    availability_wait: stats.availability_wait,
+                    delay: stats.delay,
+                    error: stats.error,
+                }
+            })
+            .collect::<Vec<_>>();
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { packages }
+    }
+
+    /// Returns the packages with the given outcome.
+    pub fn with_outcome(&self, outcome: Outcome) -> impl Iterator<Item = &PackageReport> {
+        self.packages
+            .iter()
+            .filter(move |package| package.outcome == outcome)
+    }
+}
+
+fn format_duration(duration: Option<Duration>) -> String {
+    duration.map_or_else(
+        || "-".to_string(),
+        |duration| humantime::format_duration(Duration::from_secs(duration.as_secs())).to_string(),
+    )
+}
+
+impl std::fmt::Display for PublishReport {
+    /// Formats the report as a table with one row per package.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        const HEADER: [&str; 8] = [
+            "package",
+            "version",
+            "outcome",
+            "attempts",
+            "upload",
+            "availability",
+            "delay",
+            "error",
+        ];
+        let rows = self
+            .packages
+            .iter()
+            .map(|package| {
+                [
+                    package.name.clone(),
+                    package.version.to_string(),
+                    package.outcome.to_string(),
+                    package.attempts.to_string(),
+                    format_duration(package.upload),
+                    format_duration(package.availability_wait),
+                    format_duration(package.delay),
+                    package
+                        .error
+                        .map_or_else(|| "-".to_string(), |error| error.to_string()),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let mut widths = HEADER.map(str::len);
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+        let header = HEADER.map(ToString::to_string);
+        for row in std::iter::once(&header).chain(&rows) {
+            let line = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Outcome, PackageReport, PublishReport};
+    use crate::{PublishError, SkipReason};
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use std::time::Duration;
+
+    #[test]
+    fn formats_report_as_table() {
+        let report = PublishReport {
+            packages: vec![
+                PackageReport {
+                    name: "core".to_string(),
+                    version: semver::Version::new(1, 2, 3),
+                    outcome: Outcome::Published,
+                    attempts: 2,
+                    upload: Some(Duration::from_millis(75_400)),
+                    availability_wait: Some(Duration::from_secs(5)),
+                    delay: Some(Duration::from_secs(30)),
+                    error: Some(PublishError::Retryable(http::StatusCode::TOO_MANY_REQUESTS)),
+                },
+                PackageReport {
+                    name: "internal".to_string(),
+                    version: semver::Version::new(0, 1, 0),
+                    outcome: Outcome::Skipped(SkipReason::NotPublishable),
+                    attempts: 0,
+                    upload: None,
+                    availability_wait: None,
+                    delay: None,
+                    error: None,
+                },
+            ],
+        };
+        sim_assert_eq!(
+            report.to_string(),
+            "\
+package   version  outcome                    attempts  upload  availability  delay  error
+core      1.2.3    published                  2         1m 15s  5s            30s    retryable (429 Too Many Requests)
+internal  0.1.0    skipped (publish = false)  0         -       -             -      -
+"
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! Version requirements written for local dependencies.
+
+use crate::Error;
+
+/// Version requirement written by [`crate::Options::resolve_versions`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VersionReqStyle {
+    /// `=1.2.3`, pinning dependants to the exact workspace version.
+    #[default]
+    Exact,
+    /// `^1.2.3`, accepting later semver-compatible versions.
+    Caret,
+    /// `~1.2.3`, accepting later patch versions.
+    Tilde,
+    /// `^1.2`, accepting any semver-compatible version of the same or a later minor release.
+    ///
+    /// Pre-release versions use [`Self::Caret`] because `^1.2` does not match them.
+    MinorCompatible,
+    /// Keeps an existing requirement if it matches the workspace version.
+    ///
+    /// Dependencies without a version requirement receive [`Self::Exact`]; existing requirements
+    /// that do not match the workspace version are rejected.
+    KeepExistingIfCompatible,
+}
+
+impl std::fmt::Display for VersionReqStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let style = match self {
+            Self::Exact => "exact",
+            Self::Caret => "caret",
+            Self::Tilde => "tilde",
+            Self::MinorCompatible => "minor-compatible",
+            Self::KeepExistingIfCompatible => "keep-existing-if-compatible",
+        };
+        write!(f, "{style}")
+    }
+}
+
+impl std::str::FromStr for VersionReqStyle {
+    type Err = Error;
+
+    fn from_str(style: &str) -> Result<Self, Self::Err> {
+        match style.to_ascii_lowercase().as_str() {
+            "exact" => Ok(Self::Exact),
+            "caret" => Ok(Self::Caret),
+            "tilde" => Ok(Self::Tilde),
+            "minor-compatible" => Ok(Self::MinorCompatible),
+            "keep-existing-if-compatible" => Ok(Self::KeepExistingIfCompatible),
+            _ => Err(Error::InvalidOptions(format!(
+                "unknown version requirement style {style:?} (expected exact, caret, tilde, minor-compatible or keep-existing-if-compatible)"
+            ))),
+        }
+    }
+}
+
+impl VersionReqStyle {
+    /// Returns the requirement on `version` to write in place of `existing`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when [`Self::KeepExistingIfCompatible`] keeps a requirement that does not
+    /// match `version`.
+    pub fn requirement(
+        self,
+        version: &semver::Version,
+        existing: Option<&semver::VersionReq>,
+    ) -> crate::Result<semver::VersionReq> {
+        let requirement = match self {
+            Self::Exact => format!("={version}"),
+            Self::Tilde => format!("~{version}"),
+            Self::MinorCompatible if version.pre.is_empty() => {
+                format!("^{}.{}", version.major, version.minor)
+            }
+            Self::Caret | Self::MinorCompatible => format!("^{version}"),
+            Self::KeepExistingIfCompatible => match existing {
+                Some(existing) if existing.matches(version) => return Ok(existing.clone()),
+                Some(existing) => {
+                    return Err(Error::IncompatibleRequirement {
+                        requirement: existing.clone(),
+                        version: version.clone(),
+                    });
+                }
+                None => format!("={version}"),
+            },
+        };
+        Ok(requirement.parse()?)
+    }
+}
+
+/// Returns the requirement [`crate::bump`] writes on `version` in place of `existing`.
+///
+/// With a `style`, the requirement is written in that style. Otherwise `existing` is kept if it
+/// matches `version`, and else upgraded to `version` with the same operator and precision, so
+/// `0.3` becomes `^0.4` and `=1.2.3` becomes `=1.2.4`. Requirements that cannot be upgraded this
+/// way, such as `>=1, <2`, become [`VersionReqStyle::Caret`].
+pub(crate) fn bumped_requirement(
+    style: Option<VersionReqStyle>,
+    version: &semver::Version,
+    existing: &semver::VersionReq,
+) -> crate::Result<semver::VersionReq> {
+    if let Some(style) = style {
+        return style.requirement(version, Some(existing));
+    }
+    if existing.matches(version) {
+        return Ok(existing.clone());
+    }
+    if let [comparator] = existing.comparators.as_slice() {
+        let upgrade = |full: bool| semver::VersionReq {
+            comparators: vec![semver::Comparator {
+                op: comparator.op,
+                major: version.major,
+                minor: (full || comparator.minor.is_some()).then_some(version.minor),
+                patch: (full || comparator.patch.is_some()).then_some(version.patch),
+                pre: if full || comparator.patch.is_some() {
+                    version.pre.clone()
+                } else {
+                    semver::Prerelease::EMPTY
+                },
+            }],
+        };
+        // Pre-releases only match requirements that name the full version.
+        if let Some(requirement) = [upgrade(false), upgrade(true)]
+            .into_iter()
+            .find(|requirement| requirement.matches(version))
+        {
+            return Ok(requirement);
+        }
+    }
+    VersionReqStyle::Caret.requirement(version, Some(existing))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VersionReqStyle;
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    #[test]
+    fn writes_requirements_in_each_style() {
+        let version = semver::Version::new(1, 2, 3);
+        let existing = "1.1".parse::<semver::VersionReq>().ok();
+        let requirement = |style: VersionReqStyle, existing: Option<&semver::VersionReq>| {
+            style
+                .requirement(&version, existing)
+                .map(|requirement| requirement.to_string())
+                .map_err(|err| err.to_string())
+        };
+
+        sim_assert_eq!(
+            requirement(VersionReqStyle::Exact, existing.as_ref()),
+            Ok("=1.2.3".to_string())
+        );
+        sim_assert_eq!(
+            requirement(VersionReqStyle::Caret, None),
+            Ok("^1.2.3".to_string())
+        );
+        sim_assert_eq!(
+            requirement(VersionReqStyle::Tilde, None),
+            Ok("~1.2.3".to_string())
+        );
+        sim_assert_eq!(
+            requirement(VersionReqStyle::MinorCompatible, None),
+            Ok("^1.2".to_string())
+        );
+        sim_assert_eq!(
+            requirement(VersionReqStyle::KeepExistingIfCompatible, existing.as_ref()),
+            Ok("^1.1".to_string())
+        );
+        sim_assert_eq!(
+            requirement(VersionReqStyle::KeepExistingIfCompatible, None),
+            Ok("=1.2.3".to_string())
+        );
+        sim_assert_eq!(
+            requirement(
+                VersionReqStyle::KeepExistingIfCompatible,
+                "=1.2.2".parse().ok().as_ref()
+            ),
+            Err("requirement =1.2.2 does not match workspace version 1.2.3".to_string())
+        );
+        sim_assert_eq!(
+            "minor-compatible".parse::<VersionReqStyle>().ok(),
+            Some(VersionReqStyle::MinorCompatible)
+        );
+    }
+
+    #[test]
+    fn bumps_requirements_with_their_operators() {
+        let bumped = |style: Option<VersionReqStyle>, version: &str, existing: &str| {
+            let version = version.parse().expect("version must parse");
+            let existing = existing.parse().expect("requirement must parse");
+            super::bumped_requirement(style, &version, &existing)
+                .expect("requirement must bump")
+                .to_string()
+        };
+
+        sim_assert_eq!(bumped(None, "0.4.0", "0.3"), "^0.4");
+        sim_assert_eq!(bumped(None, "1.2.4", "=1.2.3"), "=1.2.4");
+        sim_assert_eq!(bumped(None, "1.3.0", "~1.2"), "~1.3");
+        sim_assert_eq!(bumped(None, "2.0.0", "1.*"), "2.*");
+        sim_assert_eq!(bumped(None, "1.3.0", "^1.2.3"), "^1.2.3");
+        sim_assert_eq!(bumped(None, "0.4.0-rc.1", "0.3"), "^0.4.0-rc.1");
+        sim_assert_eq!(bumped(None, "2.1.0", ">=1, <2"), "^2.1.0");
+        sim_assert_eq!(
+            bumped(Some(VersionReqStyle::Exact), "0.4.0", "0.3"),
+            "=0.4.0"
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Backoff between `cargo publish` attempts.
+
+use crate::{Error, FailureCategory, PublishError};
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+
+/// Class of a failed `cargo publish` attempt that [`RetryPolicy::class_delays`] can configure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RetryClass {
+    /// The registry rejected the upload with `429 Too Many Requests`.
+    RateLimited,
+    /// Any other transient HTTP error, or a known transient failure such as a network error.
+    Retryable,
+    /// An error without a recognized HTTP status code.
+    Unknown,
+}
+
+impl RetryClass {
+    /// Returns the class of a retryable publishing error, or [`None`] for fatal errors.
+    #[must_use]
+    pub fn of(error: &PublishError) -> Option<Self> {
+        match error {
+            PublishError::Retryable(http::StatusCode::TOO_MANY_REQUESTS) => Some(Self::RateLimited),
+            PublishError::Retryable(_) => Some(Self::Retryable),
+            PublishError::Unknown => Some(Self::Unknown),
+            PublishError::Known(failure) => {
+                (failure.category() == FailureCategory::Retryable).then_some(Self::Retryable)
+            }
+            PublishError::Fatal(_) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for RetryClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let class = match self {
+            Self::RateLimited => "rate-limited",
+            Self::Retryable => "retryable",
+            Self::Unknown => "unknown",
+        };
+        write!(f, "{class}")
+    }
+}
+
+impl std::str::FromStr for RetryClass {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "rate-limited" => Ok(Self::RateLimited),
+            "retryable" => Ok(Self::Retryable),
+            "unknown" => Ok(Self::Unknown),
+            _ => Err(Error::InvalidOptions(format!(
+                "unknown retry class {s:?} (expected rate-limited, retryable or unknown)"
+            ))),
+        }
+    }
+}
+
+/// Exponential backoff with jitter between attempts of a package.
+///
+/// The `n`-th retry of a failure waits `initial_delay * multiplier^(n - 1)`, capped at
+/// [`Self::max_delay`] and then varied by up to [`Self::jitter`] in either direction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Delay before the first retry.
+    pub initial_delay: Duration,
+    /// Factor applied to the delay after every retry. Must be at least one.
+    pub multiplier: f64,
+    /// Upper bound of a delay before jitter.
+    pub max_delay: Duration,
+    /// Fraction of every delay that is randomized, between zero and one.
+    ///
+    /// Jitter spreads out retries of packages that failed at the same time.
+    pub jitter: f64,
+    /// Maximum total time a package spends waiting between attempts.
+    ///
+    /// [`None`] only limits the number of attempts by [`crate::Options::max_retries`].
+    pub budget: Option<Duration>,
+    /// Initial delays that replace [`Self::initial_delay`] for a class of failures.
+    pub class_delays: BTreeMap<RetryClass, Duration>,
+}
+
+impl Default for RetryPolicy {
+    /// Starts at 30 seconds and doubles up to 10 minutes with 10% jitter, except that rate
+    /// limits wait 10 minutes right away.
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+            max_delay: Duration::from_mins(10),
+            jitter: 0.1,
+            budget: None,
+            class_delays: BTreeMap::from([(RetryClass::RateLimited, Duration::from_mins(10))]),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub(crate) fn validate(&self) -> crate::Result<()> {
+        if !(self.multiplier >= 1.0 && self.multiplier.is_finite()) {
+            return Err(Error::InvalidOptions(
+                "retry multiplier must be at least one".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.jitter) {
+            return Err(Error::InvalidOptions(
+                "retry jitter must be between zero and one".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the delay before the one-based `retry` of a failure of `class`, without jitter.
+    #[must_use]
+    pub fn delay(&self, class: RetryClass, retry: usize) -> Duration {
+        let initial = self
+            .class_delays
+            .get(&class)
+            .copied()
+            .unwrap_or(self.initial_delay);
+        let exponent = i32::try_from(retry.saturating_sub(1)).unwrap_or(i32::MAX);
+        let secs = initial.as_secs_f64() * self.multiplier.powi(exponent);
+        Duration::try_from_secs_f64(secs)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+
+    /// Returns `delay` varied randomly by up to [`Self::jitter`].
+    pub(crate) fn jittered(&self, delay: Duration) -> Duration {
+        if self.jitter <= 0.0 {
+            return delay;
+        }
+        let factor = rand::random_range((1.0 - self.jitter)..=(1.0 + self.jitter));
+        Duration::try_from_secs_f64(delay.as_secs_f64() * factor).unwrap_or(delay)
+    }
+}
+
+/// Parses an HTTP date such as `Fri, 17 Oct 2025 12:00:00 GMT`, or an RFC 3339 timestamp.
+fn parse_date(value: &str) -> Option<SystemTime> {
+    let value = value.trim().trim_end_matches(['.', ',']);
+    time::OffsetDateTime::parse(value, &time::format_description::well_known::Rfc2822)
+        .map(SystemTime::from)
+        .ok()
+        .or_else(|| humantime::parse_rfc3339_weak(value).ok())
+}
+
+/// Returns the time a registry asked to retry at in `cargo publish` output.
+///
+/// crates.io rejects new crates over its rate limit with `Please try again after <date> or email
+/// help@crates.io to have your limit increased.`
+pub(crate) fn retry_after_message(text: &str) -> Option<SystemTime> {
+    const NEEDLE: &str = "try again after ";
+    text.lines().find_map(|line| {
+        let start = line.to_ascii_lowercase().find(NEEDLE)? + NEEDLE.len();
+        let rest = line.get(start..)?;
+        let date = rest.split(" or ").next().unwrap_or(rest);
+        parse_date(date)
+    })
+}
+
+/// Parses a `Retry-After` header holding either delay seconds or an HTTP date.
+pub(crate) fn retry_after_header(value: &str, now: SystemTime) -> Option<SystemTime> {
+    match value.trim().parse::<u64>() {
+        Ok(secs) => now.checked_add(Duration::from_secs(secs)),
+        Err(_) => parse_date(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RetryClass, RetryPolicy};
+    use crate::{KnownFailure, PublishError};
+    use similar_asserts::assert_eq as sim_assert_eq;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn parses_retry_after_hints() {
+        let at = humantime::parse_rfc3339("2025-10-17T12:00:00Z").expect("timestamp must parse");
+        let stderr = "error: failed to publish to registry at https://crates.io
+
+Caused by:
+  the remote server responded with an error (status 429 Too Many Requests): You have published \
+too many new crates in a short period of time. Please try again after Fri, 17 Oct 2025 12:00:00 \
+GMT or email help@crates.io to have your limit increased.";
+        sim_assert_eq!(super::retry_after_message(stderr), Some(at));
+        sim_assert_eq!(
+            super::retry_after_message("Please try again after 2025-10-17T12:00:00Z."),
+            Some(at)
+        );
+        sim_assert_eq!(super::retry_after_message("try again later"), None);
+
+        let now = SystemTime::UNIX_EPOCH;
+        sim_assert_eq!(
+            super::retry_after_header("120", now),
+            Some(now + Duration::from_mins(2))
+        );
+        sim_assert_eq!(
+            super::retry_after_header("Fri, 17 Oct 2025 12:00:00 GMT", now),
+            Some(at)
+        );
+        sim_assert_eq!(super::retry_after_header("soon", now), None);
+    }
+
+    #[test]
+    fn grows_delays_exponentially_up_to_the_cap() {
+        let policy = RetryPolicy::default();
+        let delays = (1..=6)
+            .map(|retry| policy.delay(RetryClass::Retryable, retry).as_secs())
+            .collect::<Vec<_>>();
+        sim_assert_eq!(delays, vec![30, 60, 120, 240, 480, 600]);
+        sim_assert_eq!(
+            policy.delay(RetryClass::RateLimited, 1),
+            Duration::from_mins(10)
+        );
+        sim_assert_eq!(
+            policy.delay(RetryClass::Unknown, usize::MAX),
+            Duration::from_mins(10)
+        );
+
+        for _ in 0..100 {
+            let delay = policy.jittered(Duration::from_secs(100));
+            assert!((90..=110).contains(&delay.as_secs()), "{delay:?}");
+        }
+        let policy = RetryPolicy {
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        };
+        sim_assert_eq!(
+            policy.jittered(Duration::from_secs(100)),
+            Duration::from_secs(100)
+        );
+    }
+
+    #[test]
+    fn classifies_and_validates() {
+        sim_assert_eq!(
+            RetryClass::of(&PublishError::Retryable(
+                http::StatusCode::TOO_MANY_REQUESTS
+            )),
+            Some(RetryClass::RateLimited)
+        );
+        sim_assert_eq!(
+            RetryClass::of(&PublishError::Fatal(http::StatusCode::FORBIDDEN)),
+            None
+        );
+        sim_assert_eq!(
+            RetryClass::of(&PublishError::Known(KnownFailure::Network)),
+            Some(RetryClass::Retryable)
+        );
+        sim_assert_eq!(
+            RetryClass::of(&PublishError::Known(KnownFailure::InvalidToken)),
+            None
+        );
+        sim_assert_eq!(
+            "Rate-Limited".parse::<RetryClass>().ok(),
+            Some(RetryClass::RateLimited)
+        );
+        assert!("slow".parse::<RetryClass>().is_err());
+
+        let policy = RetryPolicy {
+            multiplier: 0.5,
+            ..RetryPolicy::default()
+        };
+        assert!(policy.validate().is_err());
+        let policy = RetryPolicy {
+            jitter: 1.5,
+            ..RetryPolicy::default()
+        };
+        assert!(policy.validate().is_err());
+        assert!(RetryPolicy::default().validate().is_ok());
+    }
+}
@@ -0,0 +1,224 @@
+//! Selection of workspace packages by name, directory and changes.
+
+use crate::log;
+use crate::{Options, SkipReason, config::PackageConfig};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+/// Returns `true` unless the manifest sets `publish = false` or an empty registry list.
+pub(crate) fn is_publishable(package: &cargo_metadata::Package) -> bool {
+    package
+        .publish
+        .as_ref()
+        .is_none_or(|registries| !registries.is_empty())
+}
+
+/// A pattern of [`Options::include`] or [`Options::exclude`].
+#[derive(Debug)]
+enum Selector {
+    /// Matches package names, such as `mylib-*`.
+    Name(globset::GlobMatcher),
+    /// Matches package directories relative to the workspace root, such as `crates/internal/**`.
+    Path(globset::GlobMatcher),
+}
+
+impl Selector {
+    /// Parses a pattern, which selects directories if it contains a `/`.
+    fn new(pattern: &str) -> crate::Result<Self> {
+        let is_path = pattern.contains('/');
+        let glob = globset::GlobBuilder::new(pattern.trim_end_matches('/'))
+            .literal_separator(true)
+            .build()
+            .map_err(|err| {
+                crate::Error::InvalidOptions(format!("invalid package selector {pattern:?}: {err}"))
+            })?
+            .compile_matcher();
+        Ok(if is_path {
+            Self::Path(glob)
+        } else {
+            Self::Name(glob)
+        })
+    }
+
+    fn matches(&self, name: &str, dir: &str) -> bool {
+        match self {
+            Self::Name(glob) => glob.is_match(name),
+            Self::Path(glob) => glob.is_match(dir),
+        }
+    }
+}
+
+/// Returns the directory of `package` relative to `workspace_root`, separated by `/`.
+fn relative_dir(
+    package: &cargo_metadata::Package,
+    workspace_root: &cargo_metadata::camino::Utf8Path,
+) -> String {
+    package
+        .manifest_path
+        .parent()
+        .and_then(|dir| dir.strip_prefix(workspace_root).ok())
+        .map(|dir| {
+            dir.components()
+                .map(|component| component.as_str())
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .unwrap_or_default()
+}
+
+/// Workspace packages deselected by [`Options::include`], [`Options::exclude`],
+/// [`Options::changed_since`] and package configuration.
+#[derive(Debug, Default)]
+pub(crate) struct Selection {
+    /// Deselected packages by name.
+    skipped: HashMap<String, SkipReason>,
+    /// Deselected publishable packages that a selected package depends on, with the first such
+    /// dependant in workspace order.
+    ///
+    /// These are published already or block their dependants.
+    required: HashMap<String, String>,
+}
+
+/// Returns the workspace members of `metadata` that local path dependencies point to, by their
+/// directory.
+fn members_by_dir(
+    metadata: &cargo_metadata::Metadata,
+) -> HashMap<PathBuf, &cargo_metadata::Package> {
+    metadata
+        .workspace_packages()
+        .into_iter()
+        .filter_map(|package| Some((PathBuf::from(package.manifest_path.parent()?), package)))
+        .collect()
+}
+
+/// Returns the publishable local packages that `package` depends on.
+fn local_dependencies<'a>(
+    package: &'a cargo_metadata::Package,
+    members: &'a HashMap<PathBuf, &'a cargo_metadata::Package>,
+) -> impl Iterator<Item = &'a cargo_metadata::Package> {
+    package.dependencies.iter().filter_map(|dependency| {
+        let path = PathBuf::from(dependency.path.as_ref()?);
+        members
+            .get(&path)
+            .copied()
+            .filter(|resolved| is_publishable(resolved))
+    })
+}
+
+impl Selection {
+    /// Selects the workspace packages of `metadata`.
+    ///
+    /// `changed` holds the packages changed since [`Options::changed_since`], if it is set.
+    pub(crate) fn new(
+        metadata: &cargo_metadata::Metadata,
+        options: &Options,
+        changed: Option<&BTreeSet<String>>,
+    ) -> crate::Result<Self> {
+        let selectors = |patterns: Option<&Vec<String>>| {
+            patterns
+                .into_iter()
+                .flatten()
+                .map(|pattern| Selector::new(pattern))
+                .collect::<crate::Result<Vec<_>>>()
+        };
+        let include = selectors(options.include.as_ref())?;
+        let exclude = selectors(options.exclude.as_ref())?;
+
+        let mut skipped = HashMap::new();
+        for package in metadata.workspace_packages() {
+            let dir = relative_dir(package, &metadata.workspace_root);
+            let matches = |selector: &Selector| selector.matches(&package.name, &dir);
+            let reason = if exclude.iter().any(matches) {
+                SkipReason::Excluded
+            } else if PackageConfig::of(package)?.skip {
+                SkipReason::Configured
+            } else if !include.is_empty() && !include.iter().any(matches) {
+                SkipReason::NotIncluded
+            } else if changed.is_some_and(|changed| !changed.contains(package.name.as_str())) {
+                SkipReason::Unchanged
+            } else {
+                continue;
+            };
+            skipped.insert(package.name.to_string(), reason);
+        }
+
+        let mut selection = Self {
+            skipped,
+            required: HashMap::new(),
+        };
+        let members = members_by_dir(metadata);
+        if options.include_dependencies.unwrap_or_default() {
+            selection.include_dependencies(&members, options.log());
+        }
+        for package in metadata.workspace_packages() {
+            if !is_publishable(package) || selection.skip_reason(&package.name).is_some() {
+                continue;
+            }
+            for resolved in local_dependencies(package, &members) {
+                if selection.skip_reason(&resolved.name).is_some() {
+                    selection
+                        .required
+                        .entry(resolved.name.to_string())
+                        .or_insert_with(|| package.name.to_string());
+                }
+            }
+        }
+        Ok(selection)
+    }
+
+    /// Selects the local dependencies of selected packages, transitively.
+    ///
+    /// Dependencies that are excluded or not publishable stay deselected.
+    fn include_dependencies(
+        &mut self,
+        members: &HashMap<PathBuf, &cargo_metadata::Package>,
+        log: log::Log,
+    ) {
+        let mut queue = members
+            .values()
+            .filter(|package| is_publishable(package) && self.skip_reason(&package.name).is_none())
+            .copied()
+            .collect::<Vec<_>>();
+        while let Some(package) = queue.pop() {
+            for resolved in local_dependencies(package, members) {
+                let name = resolved.name.as_str();
+                if !matches!(
+                    self.skipped.get(name),
+                    Some(SkipReason::NotIncluded | SkipReason::Unchanged)
+                ) {
+                    continue;
+                }
+                self.skipped.remove(name);
+                log::info!(log, "including {name} as a dependency of {}", package.name);
+                queue.push(resolved);
+            }
+        }
+    }
+
+    /// Returns why the package `name` is deselected, if it is.
+    pub(crate) fn skip_reason(&self, name: &str) -> Option<SkipReason> {
+        self.skipped.get(name).copied()
+    }
+
+    /// Returns a selected package that depends on the deselected package `name`, if any.
+    pub(crate) fn required_by(&self, name: &str) -> Option<&str> {
+        self.required.get(name).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Selector;
+
+    #[test]
+    fn matches_names_and_directories() {
+        let selector = |pattern: &str| Selector::new(pattern).expect("selector must parse");
+        assert!(selector("mylib-*").matches("mylib-core", "crates/core"));
+        assert!(!selector("mylib-*").matches("other", "crates/mylib-core"));
+        assert!(selector("crates/internal/**").matches("tools", "crates/internal/tools"));
+        assert!(selector("crates/internal/*/").matches("tools", "crates/internal/tools"));
+        assert!(!selector("crates/*").matches("tools", "crates/internal/tools"));
+        assert!(selector("core").matches("core", "crates/core"));
+        assert!(Selector::new("crates/[").is_err());
+    }
+}
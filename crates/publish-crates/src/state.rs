@@ -0,0 +1,175 @@
+//! On-disk record of package progress for resuming interrupted publish runs.
+
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Publishing progress of a package.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageState {
+    /// The package has not been published yet.
+    #[default]
+    Pending,
+    /// `cargo publish` is running.
+    Publishing,
+    /// `cargo publish` succeeded; the registry may not serve the version yet.
+    Uploaded,
+    /// The registry serves the version.
+    Available,
+    /// The package is published and its dependants may proceed.
+    Done,
+}
+
+impl std::fmt::Display for PackageState {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let state = match self {
+            Self::Pending => "pending",
+            Self::Publishing => "publishing",
+            Self::Uploaded => "uploaded",
+            Self::Available => "available",
+            Self::Done => "done",
+        };
+        write!(f, "{state}")
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct PackageEntry {
+    version: String,
+    state: PackageState,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct StateFile {
+    packages: BTreeMap<String, PackageEntry>,
+}
+
+/// Records package states and persists them after every transition.
+#[derive(Debug)]
+pub(crate) struct StateStore {
+    path: Option<PathBuf>,
+    previous: StateFile,
+    current: Mutex<StateFile>,
+}
+
+impl StateStore {
+    /// Creates a store that only tracks states in memory.
+    pub(crate) fn in_memory() -> Self {
+        Self {
+            path: None,
+            previous: StateFile::default(),
+            current: Mutex::new(StateFile::default()),
+        }
+    }
+
+    /// Creates a store that persists to `path`.
+    ///
+    /// With `resume`, the states of the previous run are loaded from `path` when it exists.
+    pub(crate) fn open(path: PathBuf, resume: bool) -> crate::Result<Self> {
+        let previous = if resume && path.is_file() {
+            let contents = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&contents).map_err(|error| crate::Error::StateFile {
+                path: path.clone(),
+                error,
+            })?
+        } else {
+            StateFile::default()
+        };
+        Ok(Self {
+            path: Some(path),
+            current: Mutex::new(previous.clone()),
+            previous,
+        })
+    }
+
+    /// Returns the state a package version reached in the previous run.
+    pub(crate) fn previous(&self, name: &str, version: &semver::Version) -> Option<PackageState> {
+        self.previous
+            .packages
+            .get(name)
+            .filter(|entry| entry.version == version.to_string())
+            .map(|entry| entry.state)
+    }
+
+    /// Records a package state and writes the state file.
+    pub(crate) fn set(
+        &self,
+        name: &str,
+        version: &semver::Version,
+        state: PackageState,
+    ) -> crate::Result<()> {
+        let mut current = self.current.lock();
+        current.packages.insert(
+            name.to_string(),
+            PackageEntry {
+                version: version.to_string(),
+                state,
+            },
+        );
+        match &self.path {
+            Some(path) => write(path, &current),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Replaces the state file through a temporary sibling so readers never observe partial JSON.
+fn write(path: &Path, state: &StateFile) -> crate::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, serde_json::to_string_pretty(state)?)?;
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PackageState, StateStore};
+    use similar_asserts::assert_eq as sim_assert_eq;
+
+    #[test]
+    fn resumes_states_of_matching_versions() {
+        let temp = tempfile::tempdir().expect("temporary directory must be created");
+        let path = temp.path().join("publish-crates").join("state.json");
+        let version = semver::Version::new(1, 2, 3);
+
+        let store = StateStore::open(path.clone(), false).expect("state file must open");
+        store
+            .set("foo", &version, PackageState::Uploaded)
+            .expect("state must be written");
+        store
+            .set("bar", &version, PackageState::Publishing)
+            .expect("state must be written");
+
+        let resumed = StateStore::open(path.clone(), true).expect("state file must load");
+        sim_assert_eq!(
+            resumed.previous("foo", &version),
+            Some(PackageState::Uploaded)
+        );
+        sim_assert_eq!(
+            resumed.previous("bar", &version),
+            Some(PackageState::Publishing)
+        );
+        sim_assert_eq!(
+            resumed.previous("foo", &semver::Version::new(1, 2, 4)),
+            None
+        );
+
+        let fresh = StateStore::open(path, false).expect("state file must open");
+        sim_assert_eq!(fresh.previous("foo", &version), None);
+    }
+}